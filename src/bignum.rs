@@ -5,6 +5,18 @@
 //! and will take at most 160 bytes of stack memory. This is more than enough
 //! for round-tripping all possible finite `f64` values.
 //!
+//! Decimal-to-float conversion needs more room than that: it has to hold all
+//! significant digits of a decimal string (up to 768 of them, see `dec2flt`)
+//! scaled by powers of ten, so it uses `Big32x200` with 6,400 bits instead.
+//!
+//! On 64-bit targets, `Big64x20` and `Big64x100` provide the same capacities
+//! with half as many digits, using `u128` for the double-width arithmetic.
+//! Multiplication and division need a quarter of the digit operations then.
+//!
+//! Binary128 needs far more room again, since its halfway points have up to
+//! 11,564 significant digits and its exponents go beyond 4,900. `Big32x1220`
+//! and `Big64x610` hold 39,040 bits, at just under 5 KB each.
+//!
 //! In principle it is possible to have multiple bignum types for different
 //! inputs, but we don't do so to avoid the code bloat. Each bignum is still
 //! tracked for the actual usages, so it normally doesn't matter.
@@ -75,13 +87,46 @@ impl_full_ops! {
     u8:  add(intrinsics::u8_add_with_overflow),  mul/div(u16);
     u16: add(intrinsics::u16_add_with_overflow), mul/div(u32);
     u32: add(intrinsics::u32_add_with_overflow), mul/div(u64);
-    // See RFC #521 for enabling this.
-    // u64: add(intrinsics::u64_add_with_overflow), mul/div(u128);
+    u64: add(intrinsics::u64_add_with_overflow), mul/div(u128);
 }
 
-/// Table of powers of 5 representable in digits. Specifically, the largest {u8, u16, u32} value
-/// that's a power of five, plus the corresponding exponent. Used in `mul_pow5`.
-const SMALL_POW5: [(u64, usize); 3] = [(125, 3), (15625, 6), (1_220_703_125, 13)];
+/// Table of powers of 5 representable in digits. Specifically, the largest {u8, u16, u32, u64}
+/// value that's a power of five, plus the corresponding exponent. Used in `mul_pow5`.
+const SMALL_POW5: [(u64, usize); 4] =
+    [(125, 3), (15625, 6), (1_220_703_125, 13), (7_450_580_596_923_828_125, 27)];
+
+/// The exponent of the smallest power of five in `LARGE_POW5`.
+pub const LARGE_POW5_E: usize = 27;
+
+/// Table of large powers of 5, namely `5^(LARGE_POW5_E * 2^i)`, as little-endian `u64` words.
+/// Used in `mul_pow5`, so it only needs a few multiplications for large exponents. The test
+/// `test_large_pow5` checks the values.
+#[rustfmt::skip]
+pub static LARGE_POW5: [&[u64]; 6] = [
+    &[0x6765c793fa10079d],
+    &[0x6664242d97d9f649, 0x29c30f1029939b14],
+    &[0x9efff7c792b260d1, 0xaeba5d5681de0ec6, 0x4f40737a410664a4, 0x06d00f7320d3846f],
+    &[
+        0x7893c5a72b416aa1, 0x2bad2beae37dc6d4, 0x7575ae4bf0fc846c, 0x83b67a3462587b14,
+        0xf7992f5502110cdb, 0xa4a23bec00deb022, 0xb85b654f8af5c5cd, 0x002e69d2818df38b,
+    ],
+    &[
+        0x89a9f297682bb941, 0x404217b1cba75d7b, 0xa1bc162bb4e519e9, 0x98715af5f7f5910a,
+        0xe3ef118c2ff53e57, 0xbc9b1734490c4543, 0x4cedcb4c2affbe4d, 0x35e34212fb14e99e,
+        0x07673ab3ece39c24, 0xd15d38e7e73115dd, 0xf8e7eac5093eed3b, 0x25227aac78a8cc80,
+        0x413da1cb3f590551, 0xab65ad44df643a55, 0xc672cd76d70b23d7, 0x0000086a3364ea62,
+    ],
+    &[
+        0xe208fcaa91e50281, 0x9b9b9060e6b42ef6, 0x5ce0b4b1b67447ad, 0xdeabca8b63195126,
+        0xdf6f5e9cf8083599, 0xbe8953b959bfdbcc, 0xe6cf0b65205e5883, 0x4acaedaf63b98154,
+        0x053a12a9fbd67a9d, 0x96de6b463d5e48c4, 0x6f6e7af45bc34246, 0x24fa7de6bee7b223,
+        0xf737755d78f14101, 0xf666e3ff0de85f20, 0xd24a845ad3b292fa, 0xc92a2a9bbac22ea6,
+        0x2648058e4ffbffd4, 0x168ad6e7bedac3d9, 0x8c738592fbe54ca9, 0x015c6e129f288e2e,
+        0x090735ca95202ba7, 0x7eb027969658f802, 0x5ec6009c967a7a08, 0xd604db6c2ebafbd3,
+        0x98e14643f5b39901, 0xb16b67e17b47a19b, 0x476d89810c92b58e, 0x09c80a130481121a,
+        0x7f96c1efbeb4f688, 0x0f55f3b8b34238dd, 0xe889a35ed2bf144f, 0x000000000046cf44,
+    ],
+];
 
 macro_rules! define_bignum {
     ($name:ident: type=$ty:ty, n=$n:expr) => {
@@ -118,7 +163,8 @@ macro_rules! define_bignum {
                 let mut sz = 0;
                 while v > 0 {
                     base[sz] = v as $ty;
-                    v >>= <$ty>::BITS;
+                    // Shifting a `u64` by 64 bits would overflow, the result is zero then.
+                    v = v.checked_shr(<$ty>::BITS).unwrap_or(0);
                     sz += 1;
                 }
                 $name { size: sz, base }
@@ -228,10 +274,17 @@ macro_rules! define_bignum {
             /// Multiplies itself by a digit-sized `other` and returns its own
             /// mutable reference.
             pub fn mul_small(&mut self, other: $ty) -> &mut $name {
+                self.mul_small_add(other, 0)
+            }
+
+            /// Multiplies itself by a digit-sized `other`, adds a digit-sized `addend` and
+            /// returns its own mutable reference. This is a single pass over the digits, with
+            /// `addend` as the initial carry.
+            pub fn mul_small_add(&mut self, other: $ty, addend: $ty) -> &mut $name {
                 use crate::bignum::FullOps;
 
                 let mut sz = self.size;
-                let mut carry = 0;
+                let mut carry = addend;
                 for a in &mut self.base[..sz] {
                     let (c, v) = (*a).full_mul(other, carry);
                     *a = v;
@@ -287,7 +340,26 @@ macro_rules! define_bignum {
             /// Multiplies itself by `5^e` and returns its own mutable reference.
             pub fn mul_pow5(&mut self, mut e: usize) -> &mut $name {
                 use core::mem;
-                use crate::bignum::SMALL_POW5;
+                use crate::bignum::{LARGE_POW5, LARGE_POW5_E, SMALL_POW5};
+
+                // Multiply with the large powers for the binary digits of `e / LARGE_POW5_E`,
+                // using the largest one as often as needed for the excess ...
+                let mut large = e / LARGE_POW5_E;
+                e %= LARGE_POW5_E;
+                let mut i = 0;
+                while large > 0 {
+                    if i == LARGE_POW5.len() - 1 {
+                        for _ in 0..large {
+                            self.mul_words(LARGE_POW5[i]);
+                        }
+                        break;
+                    }
+                    if large & 1 == 1 {
+                        self.mul_words(LARGE_POW5[i]);
+                    }
+                    large >>= 1;
+                    i += 1;
+                }
 
                 // There are exactly n trailing zeros on 2^n, and the only relevant digit sizes
                 // are consecutive powers of two, so this is well suited index for the table.
@@ -295,7 +367,7 @@ macro_rules! define_bignum {
                 let (small_power, small_e) = SMALL_POW5[table_index];
                 let small_power = small_power as $ty;
 
-                // Multiply with the largest single-digit power as long as possible ...
+                // ... then with the largest single-digit power as long as possible ...
                 while e >= small_e {
                     self.mul_small(small_power);
                     e -= small_e;
@@ -311,6 +383,52 @@ macro_rules! define_bignum {
                 self
             }
 
+            /// Multiplies itself by a number given as little-endian `u64` words, in place.
+            ///
+            /// This is `mul_digits` for `LARGE_POW5`, which is shared by all digit types and
+            /// would first have to be copied into digits of this one. `mul_digits` also builds
+            /// the product in a second bignum-sized array, which is another 7 KB of stack for
+            /// the largest bignums, plus a copy of all of it, on every call.
+            fn mul_words(&mut self, words: &[u64]) -> &mut $name {
+                use core::cmp;
+                use crate::bignum::FullOps;
+
+                let digitbits = <$ty>::BITS as usize;
+                let per_word = 64 / digitbits;
+                let digit = |j: usize| (words[j / per_word] >> (j % per_word * digitbits)) as $ty;
+                let mut len = words.len() * per_word;
+                while len > 0 && digit(len - 1) == 0 {
+                    len -= 1;
+                }
+
+                // Going from the most significant digit down, each digit is replaced by its
+                // product with `words`, which only affects the digits already done.
+                let mut sz = self.size;
+                for i in (0..self.size).rev() {
+                    let a = self.base[i];
+                    if a == 0 {
+                        continue;
+                    }
+                    self.base[i] = 0;
+                    let mut carry = 0;
+                    for (j, b) in self.base[i..i + len].iter_mut().enumerate() {
+                        let (c, v) = a.full_mul_add(digit(j), *b, carry);
+                        *b = v;
+                        carry = c;
+                    }
+                    let mut k = i + len;
+                    while carry > 0 {
+                        let (c, v) = self.base[k].full_add(carry, false);
+                        self.base[k] = v;
+                        carry = c as $ty;
+                        k += 1;
+                    }
+                    sz = cmp::max(sz, k);
+                }
+                self.size = sz;
+                self
+            }
+
             /// Multiplies itself by a number described by `other[0] + other[1] * 2^W +
             /// other[2] * 2^(2W) + ...` (where `W` is the number of bits in the digit type)
             /// and returns its own mutable reference.
@@ -373,9 +491,115 @@ macro_rules! define_bignum {
             /// Divide self by another bignum, overwriting `q` with the quotient and `r` with the
             /// remainder.
             pub fn div_rem(&self, d: &$name, q: &mut $name, r: &mut $name) {
+                // Knuth's Algorithm D, from The Art of Computer Programming Vol. 2, section 4.3.1,
+                // finding one digit of the quotient at a time.
+                use crate::bignum::FullOps;
+
+                assert!(!d.is_zero());
+                let digitbits = <$ty>::BITS as usize;
+                for digit in &mut q.base[..] {
+                    *digit = 0;
+                }
+                for digit in &mut r.base[..] {
+                    *digit = 0;
+                }
+                r.size = d.size;
+                q.size = 1;
+                // `size` may include leading zero digits, which the algorithm can't have.
+                let n = d.size - d.digits().iter().rev().take_while(|&&x| x == 0).count();
+                let m = self.size - self.digits().iter().rev().take_while(|&&x| x == 0).count();
+                if m < n {
+                    r.base[..m].copy_from_slice(&self.base[..m]);
+                    return;
+                }
+                if n == 1 {
+                    let mut borrow = 0;
+                    for i in (0..m).rev() {
+                        let (qd, rd) = self.base[i].full_div_rem(d.base[0], borrow);
+                        q.base[i] = qd;
+                        borrow = rd;
+                    }
+                    r.base[0] = borrow;
+                } else {
+                    // Normalize, such that the most significant digit of the divisor has its
+                    // highest bit set. The quotient stays the same, and the remainder is shifted
+                    // back at the end. The double shift right is zero for `s == 0`.
+                    let s = d.base[n - 1].leading_zeros() as usize;
+                    let mut v = [0; $n];
+                    for i in (1..n).rev() {
+                        v[i] = d.base[i] << s | d.base[i - 1] >> 1 >> (digitbits - 1 - s);
+                    }
+                    v[0] = d.base[0] << s;
+                    let mut u = [0; $n + 1];
+                    u[m] = self.base[m - 1] >> 1 >> (digitbits - 1 - s);
+                    for i in (1..m).rev() {
+                        u[i] = self.base[i] << s | self.base[i - 1] >> 1 >> (digitbits - 1 - s);
+                    }
+                    u[0] = self.base[0] << s;
+
+                    for j in (0..=m - n).rev() {
+                        // Estimate the quotient digit from the two leading digits of the
+                        // remainder, and the leading digit of the divisor. The estimate is at
+                        // most two too large, and the second digit of the divisor almost
+                        // always fixes it up.
+                        let (mut qhat, mut rhat, mut overflow) = if u[j + n] == v[n - 1] {
+                            let (c, rhat) = u[j + n - 1].full_add(v[n - 1], false);
+                            (<$ty>::MAX, rhat, c)
+                        } else {
+                            let (qhat, rhat) = u[j + n - 1].full_div_rem(v[n - 1], u[j + n]);
+                            (qhat, rhat, false)
+                        };
+                        while !overflow && qhat.full_mul(v[n - 2], 0) > (rhat, u[j + n - 2]) {
+                            qhat -= 1;
+                            let (c, x) = rhat.full_add(v[n - 1], false);
+                            rhat = x;
+                            overflow = c;
+                        }
+
+                        // Subtract `qhat` times the divisor from the current remainder.
+                        let mut carry = 0;
+                        let mut noborrow = true;
+                        for i in 0..n {
+                            let (c, p) = qhat.full_mul(v[i], carry);
+                            let (nb, x) = u[i + j].full_add(!p, noborrow);
+                            u[i + j] = x;
+                            carry = c;
+                            noborrow = nb;
+                        }
+                        let (nb, x) = u[j + n].full_add(!carry, noborrow);
+                        u[j + n] = x;
+                        if !nb {
+                            // Rarely, the estimate is still one too large. Add the divisor back.
+                            qhat -= 1;
+                            let mut carry = false;
+                            for i in 0..n {
+                                let (c, x) = u[i + j].full_add(v[i], carry);
+                                u[i + j] = x;
+                                carry = c;
+                            }
+                            u[j + n] = u[j + n].full_add(0, carry).1;
+                        }
+                        q.base[j] = qhat;
+                    }
+
+                    for i in 0..n {
+                        r.base[i] = u[i] >> s | u[i + 1] << 1 << (digitbits - 1 - s);
+                    }
+                }
+                if let Some(top) = q.base[..m].iter().rposition(|&x| x != 0) {
+                    q.size = top + 1;
+                }
+                debug_assert!(q.base[q.size..].iter().all(|&d| d == 0));
+                debug_assert!(r.base[r.size..].iter().all(|&d| d == 0));
+            }
+
+            /// Divide self by another bignum with base-2 long division, one bit at a time.
+            ///
+            /// This is much slower than `div_rem`, but simple enough to serve as a reference
+            /// implementation in tests.
+            pub fn div_rem_bitwise(&self, d: &$name, q: &mut $name, r: &mut $name) {
                 // Stupid slow base-2 long division taken from
                 // https://en.wikipedia.org/wiki/Division_algorithm
-                // FIXME use a greater base ($ty) for the long division.
                 assert!(!d.is_zero());
                 let digitbits = <$ty>::BITS as usize;
                 for digit in &mut q.base[..] {
@@ -457,6 +681,15 @@ macro_rules! define_bignum {
 pub type Digit32 = u32;
 
 define_bignum!(Big32x40: type=Digit32, n=40);
+define_bignum!(Big32x200: type=Digit32, n=200);
+define_bignum!(Big32x1220: type=Digit32, n=1220);
+
+/// The digit type for `Big64x20`.
+pub type Digit64 = u64;
+
+define_bignum!(Big64x20: type=Digit64, n=20);
+define_bignum!(Big64x100: type=Digit64, n=100);
+define_bignum!(Big64x610: type=Digit64, n=610);
 
 // this one is used for testing only.
 #[doc(hidden)]
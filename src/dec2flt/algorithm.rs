@@ -1,7 +1,9 @@
 //! The various algorithms from the paper.
 
 use core::cmp::min;
-use core::cmp::Ordering::{Equal, Greater, Less};
+use core::cmp::Ordering::{self, Equal, Greater, Less};
+use core::convert::TryInto;
+use core::num::FpCategory::{Infinite, Subnormal, Zero};
 use crate::dec2flt::num::{self, Big};
 use crate::dec2flt::rawfp::{self, fp_to_float, next_float, prev_float, RawFloat, Unpacked};
 use crate::dec2flt::table;
@@ -109,19 +111,53 @@ mod fpu_precision {
 /// The fast path of Bellerophon using machine-sized integers and floats.
 ///
 /// This is extracted into a separate function so that it can be attempted before constructing
-/// a bignum.
+/// a bignum. Besides inputs where both the digits and the power of ten are exact floats, this
+/// also covers integers written with a larger exponent than the powers of ten we have.
+///
+/// Types narrower than `f64` additionally take a detour through the `f64` fast path, see
+/// `narrow_from_f64`. For `f32`, this means the fast path applies whenever `f * 10^e` with the
+/// decimal digits `f` can be rewritten as `f' * 10^e'` with `f' < 2^53` and `-22 <= e' <= 22`,
+/// unless the `f64` result happens to be exactly halfway between two `f32`. In particular, it
+/// covers every input with at most 15 significant digits and an exponent between -22 and 22,
+/// where the `f32` arithmetic alone stops at 7 digits and an exponent between -10 and 10.
+/// Types without hardware arithmetic, like `F16` and `BF16`, only take the detour.
 pub fn fast_path<T: RawFloat>(integral: &[u8], fractional: &[u8], e: i64) -> Option<T> {
     let num_digits = integral.len() + fractional.len();
     // log_10(f64::MAX_SIG) ~ 15.95. We compare the exact value to MAX_SIG near the end,
     // this is just a quick, cheap rejection (and also frees the rest of the code from
-    // worrying about underflow).
-    if num_digits > 16 {
+    // worrying about underflow). The exponent check is a similar rejection, since no type has
+    // more exact negative powers of ten than `f64`.
+    if num_digits > 16 || e < -(<f64 as RawFloat>::CEIL_LOG5_OF_MAX_SIG as i64 - 1) {
         return None;
     }
-    if e.abs() >= T::CEIL_LOG5_OF_MAX_SIG as i64 {
+    let f = num::append_digits(num::from_str_unchecked(integral), fractional);
+    if T::NATIVE_ARITHMETIC {
+        if let Some(x) = exact_fast_path(f, e) {
+            return Some(x);
+        }
+    }
+    if T::SIG_BITS < <f64 as RawFloat>::SIG_BITS {
+        return narrow_from_f64(exact_fast_path::<f64>(f, e)?);
+    }
+    None
+}
+
+/// Computes `f * 10^e` with a single floating point operation in `T`, if both operands are exact.
+fn exact_fast_path<T: RawFloat>(mut f: u64, mut e: i64) -> Option<T> {
+    let max_e = T::CEIL_LOG5_OF_MAX_SIG as i64 - 1;
+    if e < -max_e {
         return None;
     }
-    let f = num::from_str_unchecked(integral.iter().chain(fractional.iter()));
+    if e > max_e {
+        // The power of ten is too large to be exact, but the value may still be an exact integer
+        // times one that isn't: 1.2345e30 is 12345 * 10^26, or 123450000 * 10^22. Since a
+        // `u64` holds at most 19 more decimal places, larger exponents are certainly out of reach.
+        if e - max_e > 19 {
+            return None;
+        }
+        f = f.checked_mul(10u64.pow((e - max_e) as u32))?;
+        e = max_e;
+    }
     if f > T::MAX_SIG {
         return None;
     }
@@ -143,6 +179,39 @@ pub fn fast_path<T: RawFloat>(integral: &[u8], fractional: &[u8], e: i64) -> Opt
     }
 }
 
+/// Rounds the correctly rounded `f64` approximation `x` of a decimal to the narrower type `T`,
+/// if that gives the same result as rounding the decimal itself.
+///
+/// As the module documentation explains, rounding twice is generally wrong. Here, however, every
+/// number halfway between two adjacent normal `T` has at most `T::SIG_BITS + 1` significant bits
+/// and is thus exactly representable in `f64`. Since rounding is monotonic, rounding the exact
+/// value to `f64` never moves it past such a halfway point, at most onto one. Therefore, unless
+/// `x` is itself halfway between two `T`, it lies between the same two halfway points as the
+/// exact value, and rounding it to `T` gives the correct result. When `x` is halfway, the exact
+/// value may or may not have been, so we give up. We also give up near the ends of the normal
+/// range of `T`, where the precision of `T` changes or the result may overflow.
+fn narrow_from_f64<T: RawFloat>(x: f64) -> Option<T> {
+    // `x` is positive, so this is the biased exponent followed by the explicit significand bits.
+    let bits = x.to_bits();
+    let explicit_bits = <f64 as RawFloat>::EXPLICIT_SIG_BITS;
+    let e = (bits >> explicit_bits) as i16 - <f64 as RawFloat>::MAX_EXP;
+    if e <= T::MIN_EXP || e >= T::MAX_EXP {
+        return None;
+    }
+    let excess = explicit_bits - T::EXPLICIT_SIG_BITS;
+    let half = 1 << (excess - 1);
+    let rest = bits & ((1 << excess) - 1);
+    if rest == half {
+        return None;
+    }
+    // Rebias the exponent and drop the excess bits. Rounding up may carry into the exponent,
+    // which is still in range.
+    let k_enc = (e + T::MAX_EXP) as u64;
+    let sig_enc = (bits & ((1 << explicit_bits) - 1)) >> excess;
+    let bits = (k_enc << T::EXPLICIT_SIG_BITS | sig_enc) + (rest > half) as u64;
+    Some(T::from_bits(bits.try_into().unwrap_or_else(|_| unreachable!())))
+}
+
 /// Algorithm Bellerophon is trivial code justified by non-trivial numeric analysis.
 ///
 /// It rounds ``f`` to a float with 64 bit significand and multiplies it by the best approximation
@@ -159,11 +228,15 @@ pub fn fast_path<T: RawFloat>(integral: &[u8], fractional: &[u8], e: i64) -> Opt
 /// > not a bound for the true error, but bounds the difference between the approximation z and
 /// > the best possible approximation that uses p bits of significand.)
 pub fn bellerophon<T: RawFloat>(f: &Big, e: i16) -> T {
+    // Rounding `f` to 64 bits, the power of ten and the product each add up to one unit in the
+    // last place of `z`, and the power of ten is only exact below 10^28. The smaller bounds from
+    // the paper for positive `e` don't hold for larger powers, which matters for values close to
+    // a halfway point. Those are about all that get here, since the fast path and both
+    // Eisel-Lemire paths settle everything else.
     let slop = if f <= &Big::from_u64(T::MAX_SIG) {
-        // The cases abs(e) < log5(2^N) are in fast_path()
-        if e >= 0 { 0 } else { 3 }
+        if e >= 0 { 2 } else { 3 }
     } else {
-        if e >= 0 { 1 } else { 4 }
+        if e >= 0 { 3 } else { 4 }
     };
     let z = rawfp::big_to_fp(f).mul(&power_of_ten(e)).normalize();
     let exp_p_n = 1 << (P - T::SIG_BITS as u32);
@@ -195,20 +268,20 @@ fn algorithm_r<T: RawFloat>(f: &Big, e: i16, z0: T) -> T {
         // power of two common to `10^e` and `2^k` to make the numbers smaller.
         make_ratio(&mut x, &mut y, e, k);
 
-        let m_digits = [(m & 0xFF_FF_FF_FF) as u32, (m >> 32) as u32];
+        let m_big = Big::from_u64(m);
         // This is written a bit awkwardly because our bignums don't support
         // negative numbers, so we use the absolute value + sign information.
-        // The multiplication with m_digits can't overflow. If `x` or `y` are large enough that
+        // The multiplication with m can't overflow. If `x` or `y` are large enough that
         // we need to worry about overflow, then they are also large enough that `make_ratio` has
         // reduced the fraction by a factor of 2^64 or more.
         let (d2, d_negative) = if x >= y {
             // Don't need x any more, save a clone().
-            x.sub(&y).mul_pow2(1).mul_digits(&m_digits);
+            x.sub(&y).mul_pow2(1).mul_digits(m_big.digits());
             (x, false)
         } else {
             // Still need y - make a copy.
             let mut y = y.clone();
-            y.sub(&x).mul_pow2(1).mul_digits(&m_digits);
+            y.sub(&x).mul_pow2(1).mul_digits(m_big.digits());
             (y, true)
         };
 
@@ -243,7 +316,7 @@ fn algorithm_r<T: RawFloat>(f: &Big, e: i16, z0: T) -> T {
 /// Given `x = f` and `y = m` where `f` represent input decimal digits as usual and `m` is the
 /// significand of a floating point approximation, make the ratio `x / y` equal to
 /// `(f * 10^e) / (m * 2^k)`, possibly reduced by a power of two both have in common.
-fn make_ratio(x: &mut Big, y: &mut Big, e: i16, k: i16) {
+pub fn make_ratio(x: &mut Big, y: &mut Big, e: i16, k: i16) {
     let (e_abs, k_abs) = (e.abs() as usize, k.abs() as usize);
     if e >= 0 {
         if k >= 0 {
@@ -272,158 +345,76 @@ fn make_ratio(x: &mut Big, y: &mut Big, e: i16, k: i16) {
     }
 }
 
-/// Conceptually, Algorithm M is the simplest way to convert a decimal to a float.
-///
-/// We form a ratio that is equal to `f * 10^e`, then throwing in powers of two until it gives
-/// a valid float significand. The binary exponent `k` is the number of times we multiplied
-/// numerator or denominator by two, i.e., at all times `f * 10^e` equals `(u / v) * 2^k`.
-/// When we have found out significand, we only need to round by inspecting the remainder of the
-/// division, which is done in helper functions further below.
-///
-/// This algorithm is super slow, even with the optimization described in `quick_start()`.
-/// However, it's the simplest of the algorithms to adapt for overflow, underflow, and subnormal
-/// results. This implementation takes over when Bellerophon and Algorithm R are overwhelmed.
-/// Detecting underflow and overflow is easy: The ratio still isn't an in-range significand,
-/// yet the minimum/maximum exponent has been reached. In the case of overflow, we simply return
-/// infinity.
+/// The slow path for everything Bellerophon can't handle: Overflow, subnormals, and inputs with
+/// too many digits for its error analysis.
 ///
-/// Handling underflow and subnormals is trickier. One big problem is that, with the minimum
-/// exponent, the ratio might still be too large for a significand. See underflow() for details.
-pub fn algorithm_m<T: RawFloat>(f: &Big, e: i16) -> T {
-    let mut u;
-    let mut v;
-    let e_abs = e.abs() as usize;
-    let mut k = 0;
-    if e < 0 {
-        u = f.clone();
-        v = Big::from_small(1);
-        v.mul_pow5(e_abs).mul_pow2(e_abs);
-    } else {
-        // FIXME possible optimization: generalize big_to_fp so that we can do the equivalent of
-        // fp_to_float(big_to_fp(u)) here, only without the double rounding.
-        u = f.clone();
-        u.mul_pow5(e_abs).mul_pow2(e_abs);
-        v = Big::from_small(1);
-    }
-    quick_start::<T>(&mut u, &mut v, &mut k);
-    let mut rem = Big::from_small(0);
-    let mut x = Big::from_small(0);
-    let min_sig = Big::from_u64(T::MIN_SIG);
-    let max_sig = Big::from_u64(T::MAX_SIG);
-    loop {
-        u.div_rem(&v, &mut x, &mut rem);
-        if k == T::MIN_EXP_INT {
-            // We have to stop at the minimum exponent, if we wait until `k < T::MIN_EXP_INT`,
-            // then we'd be off by a factor of two. Unfortunately this means we have to special-
-            // case normal numbers with the minimum exponent.
-            // FIXME find a more elegant formulation, but run the `tiny-pow10` test to make sure
-            // that it's actually correct!
-            if x >= min_sig && x <= max_sig {
-                break;
-            }
-            return underflow(x, v, rem);
-        }
-        if k > T::MAX_EXP_INT {
-            return T::INFINITY;
+/// Starting from an approximation `z0` that is at most a few units in the last place off, we
+/// construct the exact halfway points between the approximation and its neighbours, and compare
+/// them with `f * 10^e`. Each comparison is a single multiplication of bignums by powers of five
+/// and two (see `make_ratio`), so this only takes a few of those when the approximation is good,
+/// unlike the repeated bignum divisions of Algorithm M. Subnormals, overflow and underflow need
+/// no special care, since the neighbours of subnormals, infinity and zero are all well-defined.
+pub fn digit_comp<T: RawFloat>(f: &Big, e: i16, z0: T) -> T {
+    let mut z = z0;
+    let mut moved_up = false;
+    // Round up while the input is above the halfway point to the next larger float, or exactly
+    // on it with an odd significand.
+    while z.classify() != Infinite {
+        let (m, k) = decompose(z);
+        match compare_with(f, e, 2 * m + 1, k - 1) {
+            Greater => {}
+            Equal if m % 2 == 1 => return next_float(z),
+            Less | Equal => break,
         }
-        if x < min_sig {
-            u.mul_pow2(1);
-            k -= 1;
-        } else if x > max_sig {
-            v.mul_pow2(1);
-            k += 1;
-        } else {
-            break;
+        z = next_float(z);
+        moved_up = true;
+    }
+    if moved_up {
+        return z;
+    }
+    // Otherwise, round down while the input is below the halfway point to the next smaller float.
+    while z.classify() != Zero {
+        let (m, k) = halfway_below(z);
+        match compare_with(f, e, m, k) {
+            Less => {}
+            Equal if z.classify() != Infinite && decompose(z).0 % 2 == 1 => return prev_float(z),
+            Greater | Equal => break,
         }
+        z = prev_float(z);
     }
-    let q = num::to_u64(&x);
-    let z = rawfp::encode_normal(Unpacked::new(q, k));
-    round_by_remainder(v, rem, q, z)
+    z
 }
 
-/// Skips over most Algorithm M iterations by checking the bit length.
-fn quick_start<T: RawFloat>(u: &mut Big, v: &mut Big, k: &mut i16) {
-    // The bit length is an estimate of the base two logarithm, and log(u / v) = log(u) - log(v).
-    // The estimate is off by at most 1, but always an under-estimate, so the error on log(u)
-    // and log(v) are of the same sign and cancel out (if both are large). Therefore the error
-    // for log(u / v) is at most one as well.
-    // The target ratio is one where u/v is in an in-range significand. Thus our termination
-    // condition is log2(u / v) being the significand bits, plus/minus one.
-    // FIXME Looking at the second bit could improve the estimate and avoid some more divisions.
-    let target_ratio = T::SIG_BITS as i16;
-    let log2_u = u.bit_length() as i16;
-    let log2_v = v.bit_length() as i16;
-    let mut u_shift: i16 = 0;
-    let mut v_shift: i16 = 0;
-    assert!(*k == 0);
-    loop {
-        if *k == T::MIN_EXP_INT {
-            // Underflow or subnormal. Leave it to the main function.
-            break;
-        }
-        if *k == T::MAX_EXP_INT {
-            // Overflow. Leave it to the main function.
-            break;
-        }
-        let log2_ratio = (log2_u + u_shift) - (log2_v + v_shift);
-        if log2_ratio < target_ratio - 1 {
-            u_shift += 1;
-            *k -= 1;
-        } else if log2_ratio > target_ratio + 1 {
-            v_shift += 1;
-            *k += 1;
-        } else {
-            break;
-        }
+/// Decomposes a finite, non-negative float into `m * 2^k`, such that the next larger float is
+/// `(m + 1) * 2^k`.
+fn decompose<T: RawFloat>(z: T) -> (u64, i16) {
+    let Unpacked { sig, k } = z.unpack();
+    match z.classify() {
+        // `unpack` gives subnormals one more bit of significand than they really have.
+        Zero | Subnormal => (sig >> 1, T::MIN_EXP_INT),
+        _ => (sig, k),
     }
-    u.mul_pow2(u_shift as usize);
-    v.mul_pow2(v_shift as usize);
 }
 
-fn underflow<T: RawFloat>(x: Big, v: Big, rem: Big) -> T {
-    if x < Big::from_u64(T::MIN_SIG) {
-        let q = num::to_u64(&x);
-        let z = rawfp::encode_subnormal(q);
-        return round_by_remainder(v, rem, q, z);
+/// Returns the point halfway between a non-zero `z` and the next smaller float as `m * 2^k`.
+fn halfway_below<T: RawFloat>(z: T) -> (u64, i16) {
+    if z.classify() == Infinite {
+        // Anything from the halfway point between `MAX` and the next power of two overflows.
+        return (2 * T::MAX_SIG + 1, T::MAX_EXP_INT - 1);
     }
-    // Ratio isn't an in-range significand with the minimum exponent, so we need to round off
-    // excess bits and adjust the exponent accordingly. The real value now looks like this:
-    //
-    //        x        lsb
-    // /--------------\/
-    // 1010101010101010.10101010101010 * 2^k
-    // \-----/\-------/ \------------/
-    //    q     trunc.    (represented by rem)
-    //
-    // Therefore, when the rounded-off bits are != 0.5 ULP, they decide the rounding
-    // on their own. When they are equal and the remainder is non-zero, the value still
-    // needs to be rounded up. Only when the rounded off bits are 1/2 and the remainder
-    // is zero, we have a half-to-even situation.
-    let bits = x.bit_length();
-    let lsb = bits - T::SIG_BITS as usize;
-    let q = num::get_bits(&x, lsb, bits);
-    let k = T::MIN_EXP_INT + lsb as i16;
-    let z = rawfp::encode_normal(Unpacked::new(q, k));
-    let q_even = q % 2 == 0;
-    match num::compare_with_half_ulp(&x, lsb) {
-        Greater => next_float(z),
-        Less => z,
-        Equal if rem.is_zero() && q_even => z,
-        Equal => next_float(z),
+    let (m, k) = decompose(z);
+    if m == T::MIN_SIG && k > T::MIN_EXP_INT {
+        // The next smaller float is in the previous binade, with half the spacing.
+        (4 * m - 1, k - 2)
+    } else {
+        (2 * m - 1, k - 1)
     }
 }
 
-/// Ordinary round-to-even, obfuscated by having to round based on the remainder of a division.
-fn round_by_remainder<T: RawFloat>(v: Big, r: Big, q: u64, z: T) -> T {
-    let mut v_minus_r = v;
-    v_minus_r.sub(&r);
-    if r < v_minus_r {
-        z
-    } else if r > v_minus_r {
-        next_float(z)
-    } else if q % 2 == 0 {
-        z
-    } else {
-        next_float(z)
-    }
+/// Compares `f * 10^e` with `m * 2^k`.
+fn compare_with(f: &Big, e: i16, m: u64, k: i16) -> Ordering {
+    let mut x = f.clone();
+    let mut y = Big::from_u64(m);
+    make_ratio(&mut x, &mut y, e, k);
+    x.cmp(&y)
 }
@@ -0,0 +1,266 @@
+//! Converting decimal strings into the 8-bit formats of the OCP microscaling specification:
+//! E4M3FN and E5M2 for elements, and E8M0 for the scales of blocks of them.
+//!
+//! These don't fit the `RawFloat` trait: E4M3FN and E8M0 have no infinities to round to, and
+//! E8M0 has neither a sign nor zero. Instead, they share the exact route of the wide formats,
+//! which only leaves the encoding to this module. Values too large for the finite numbers of a
+//! format are handled according to an `OverflowPolicy`.
+
+use core::fmt;
+
+use super::parse::{Decimal, ZeroCounts};
+use super::rawfp::{RawFloat, Unpacked};
+use super::{convert, pfe_invalid};
+use super::wide::{self, Number, Scaled};
+use super::{FloatErrorKind, ParseFloatError};
+
+/// What to do with values beyond the largest finite number of a format, including infinity
+/// in formats without infinities.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Use the largest finite value of the same sign, like the saturating conversions of the
+    /// OCP specification. Zero and negative values, which E8M0 can't represent, become its
+    /// smallest value.
+    Saturate,
+    /// Round to infinity, as with IEEE 754 formats. In formats without infinities, use NaN
+    /// instead, like the non-saturating conversions of the OCP specification.
+    Infinity,
+    /// Always use NaN.
+    Nan,
+    /// Fail with `FloatErrorKind::OutOfRange`.
+    Error,
+}
+
+/// An 8-bit number with 4 exponent bits, 3 significand bits and no infinities ("FN" stands
+/// for finite), whose largest value is 448. Both NaNs have all other bits set.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct E4M3FN(u8);
+
+/// An 8-bit number with 5 exponent bits and 2 significand bits, which otherwise follows the
+/// rules of IEEE 754. Its largest finite value is 57344.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct E5M2(u8);
+
+/// An unsigned 8-bit power of two with a bias of 127, from 2<sup>-127</sup> to
+/// 2<sup>127</sup>, plus a single NaN with all bits set.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct E8M0(u8);
+
+/// The parameters of one of the 8-bit formats, as bits of positive values.
+struct Format {
+    /// The number of bits in the significand, *including* the hidden bit.
+    sig_bits: u32,
+    /// The exponent of the smallest normal number.
+    min_exp: i64,
+    /// Whether the top bit is the sign. Unsigned formats consist of powers of two only.
+    signed: bool,
+    max: u8,
+    infinity: Option<u8>,
+    nan: u8,
+}
+
+const E4M3FN_FORMAT: Format =
+    Format { sig_bits: 4, min_exp: -6, signed: true, max: 0x7e, infinity: None, nan: 0x7f };
+
+const E5M2_FORMAT: Format =
+    Format { sig_bits: 3, min_exp: -14, signed: true, max: 0x7b, infinity: Some(0x7c), nan: 0x7e };
+
+const E8M0_FORMAT: Format =
+    Format { sig_bits: 1, min_exp: -127, signed: false, max: 0xfe, infinity: None, nan: 0xff };
+
+impl Format {
+    /// The place value of the least significant bit of subnormals.
+    const fn min_exp_int(&self) -> i64 {
+        self.min_exp - (self.sig_bits as i64 - 1)
+    }
+
+    /// Scales the unsigned decimal for `encode`, usually by way of the nearest `f64`.
+    ///
+    /// This is the argument of `algorithm::narrow_from_f64`: every number halfway between two
+    /// adjacent values of these formats, including the threshold of overflow, is an `f64`, and
+    /// rounding is monotonic. So if the exact value rounds to `x`, it lies between the same two
+    /// halfway points as `x`, unless `x` is one of them. Then the numbers just below and just
+    /// above `x` encode differently, and only the exact route can tell which side is right.
+    fn scale(&self, decimal: Decimal<'_>, zeros: ZeroCounts) -> Scaled {
+        let x: f64 = convert(decimal.clone(), zeros);
+        if x.is_infinite() {
+            return Scaled::Huge;
+        } else if x == 0.0 {
+            let mut digits = decimal.integral.iter().chain(decimal.fractional);
+            return if digits.all(|&d| d == b'0') { Scaled::Zero } else { Scaled::Tiny };
+        }
+        // Two more bits put the numbers just below and just above `x` strictly between it and
+        // its neighbours, none of which are halfway points.
+        let Unpacked { sig, k } = x.unpack();
+        let (q, e) = ((sig as u128) << 2, k as i64 - 2);
+        let below = self.encode(Scaled::Bits { q: q - 1, e, inexact: true }, false);
+        let above = self.encode(Scaled::Bits { q, e, inexact: true }, false);
+        if below == above {
+            return Scaled::Bits { q, e, inexact: true };
+        }
+        wide::scale(decimal, self.sig_bits)
+    }
+
+    /// Encodes the unsigned value. If it can't be represented, returns the closest value that
+    /// can instead, for saturation.
+    fn encode(&self, abs: Scaled, negative: bool) -> Result<u8, u8> {
+        if !self.signed {
+            return self.encode_power_of_two(abs, negative);
+        }
+        let (q, e, inexact) = match abs {
+            Scaled::Zero | Scaled::Tiny => return Ok(0),
+            Scaled::Huge => return Err(self.max),
+            Scaled::Bits { q, e, inexact } => (q, e, inexact),
+        };
+        let min_exp_int = self.min_exp_int();
+        let (sig, unit) = wide::round(q, e, inexact, self.sig_bits, min_exp_int);
+        // See `wide::convert`, but here the numbers run on past the largest finite one.
+        let bits = (((unit - min_exp_int) as u128) << (self.sig_bits - 1)) + sig;
+        if bits <= self.max as u128 { Ok(bits as u8) } else { Err(self.max) }
+    }
+
+    /// Like `encode`, but for formats without significand bits, zero and subnormals.
+    fn encode_power_of_two(&self, abs: Scaled, negative: bool) -> Result<u8, u8> {
+        if negative {
+            return Err(0);
+        }
+        let (q, e, inexact) = match abs {
+            Scaled::Zero => return Err(0),
+            // Without zero, the smallest value is the closest one.
+            Scaled::Tiny => return Ok(0),
+            Scaled::Huge => return Err(self.max),
+            Scaled::Bits { q, e, inexact } => (q, e, inexact),
+        };
+        // The value lies in `[2^top, 2^(top + 1))`, with the midpoint at `1.5 * 2^top`. There's
+        // no significand to be even, so ties go to the even exponent, as encoded.
+        let len = 128 - q.leading_zeros();
+        let mut biased = e + len as i64 - 1 - self.min_exp;
+        let half = 1 << (len - 2);
+        let rest = q & ((1 << (len - 1)) - 1);
+        if rest > half || (rest == half && (inexact || biased % 2 != 0)) {
+            biased += 1;
+        }
+        if biased > self.max as i64 {
+            Err(self.max)
+        } else {
+            Ok(biased.max(0) as u8)
+        }
+    }
+
+    /// Converts the bits to an `f32`, which is always exact.
+    fn to_f32(&self, bits: u8) -> f32 {
+        let (negative, abs) =
+            if self.signed { (bits & 0x80 != 0, bits & 0x7f) } else { (false, bits) };
+        let value = if Some(abs) == self.infinity {
+            f32::INFINITY
+        } else if abs > self.max {
+            f32::NAN
+        } else if !self.signed {
+            pow2(abs as i64 + self.min_exp)
+        } else {
+            let shift = self.sig_bits - 1;
+            let exp = (abs >> shift) as i64;
+            let sig = abs & ((1 << shift) - 1);
+            // Subnormals have the same scale as the smallest normal numbers, but no hidden bit.
+            match exp {
+                0 => sig as f32 * pow2(self.min_exp_int()),
+                _ => (sig | 1 << shift) as f32 * pow2(exp - 1 + self.min_exp_int()),
+            }
+        };
+        if negative { -value } else { value }
+    }
+}
+
+/// Returns `2^k` for `-149 <= k <= 127`.
+fn pow2(k: i64) -> f32 {
+    if k >= -126 {
+        f32::from_bits(((k + 127) as u32) << 23)
+    } else {
+        f32::from_bits(1 << (k + 149))
+    }
+}
+
+macro_rules! fp8_type {
+    ($name:ident, $format:ident, $parse:ident) => {
+        impl $name {
+            /// The largest finite value.
+            pub const MAX: $name = $name($format.max);
+            /// Not a number, the positive one in the signed formats.
+            pub const NAN: $name = $name($format.nan);
+
+            /// Performs a raw transmutation from an integer.
+            pub const fn from_bits(v: u8) -> $name {
+                $name(v)
+            }
+
+            /// Performs a raw transmutation to an integer.
+            pub const fn to_bits(self) -> u8 {
+                self.0
+            }
+
+            /// Converts to an `f32`, which is always exact.
+            pub fn to_f32(self) -> f32 {
+                $format.to_f32(self.0)
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::Debug::fmt(&self.to_f32(), f)
+            }
+        }
+
+        impl fmt::LowerExp for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                fmt::LowerExp::fmt(&self.to_f32(), f)
+            }
+        }
+
+        /// Converts a decimal string into the nearest number of the format, or handles it
+        /// according to `overflow` if it is out of range.
+        ///
+        /// This accepts the same syntax as `dec2flt`. The rare inputs that the nearest `f64`
+        /// can't decide take the exact route of `dec2flt_f128`, with its stack usage.
+        pub fn $parse(s: &str, overflow: OverflowPolicy) -> Result<$name, ParseFloatError> {
+            dec2flt_fp8(s.as_bytes(), &$format, overflow).map($name)
+        }
+    };
+}
+
+fp8_type!(E4M3FN, E4M3FN_FORMAT, dec2flt_e4m3fn);
+fp8_type!(E5M2, E5M2_FORMAT, dec2flt_e5m2);
+fp8_type!(E8M0, E8M0_FORMAT, dec2flt_e8m0);
+
+impl E5M2 {
+    /// Positive infinity.
+    pub const INFINITY: E5M2 = E5M2(0x7c);
+}
+
+/// Like `dec2flt_with`, but for the formats in this module, whose bits are returned.
+fn dec2flt_fp8(
+    s: &[u8],
+    format: &Format,
+    overflow: OverflowPolicy,
+) -> Result<u8, ParseFloatError> {
+    let (negative, number) = wide::parse_number(s)?;
+    let sign_bit = if negative && format.signed { 0x80 } else { 0 };
+    let abs = match number {
+        Number::Decimal(decimal, zeros) => format.scale(decimal, zeros),
+        Number::Huge => Scaled::Huge,
+        Number::Tiny => Scaled::Tiny,
+        Number::Infinity => match format.infinity {
+            Some(infinity) => return Ok(infinity | sign_bit),
+            None => Scaled::Huge,
+        },
+        Number::Nan => return Ok(format.nan | sign_bit),
+    };
+    match format.encode(abs, negative) {
+        Ok(bits) => Ok(bits | sign_bit),
+        Err(closest) => match overflow {
+            OverflowPolicy::Saturate => Ok(closest | sign_bit),
+            OverflowPolicy::Infinity => Ok(format.infinity.unwrap_or(format.nan) | sign_bit),
+            OverflowPolicy::Nan => Ok(format.nan | sign_bit),
+            OverflowPolicy::Error => Err(pfe_invalid(FloatErrorKind::OutOfRange, 0)),
+        },
+    }
+}
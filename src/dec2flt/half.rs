@@ -0,0 +1,215 @@
+//! Floating point types that the toolchain doesn't provide, stored as their bit patterns.
+//!
+//! They exist to be parsed into, so they offer little beyond what the `RawFloat` trait needs.
+//! In particular, their arithmetic operators are emulated with `f32`, so the fast path leaves
+//! them alone and rounds its `f64` result instead.
+
+use core::fmt;
+use core::ops::{Div, Mul, Neg};
+
+/// An IEEE 754 binary16 ("half precision") number.
+///
+/// Values convert losslessly to `f32`, which also takes care of formatting. The arithmetic
+/// operators compute in `f32` and round the result to `F16`. Since `f32` has more than twice
+/// the precision of `F16` plus two bits, the second rounding never changes the result of a
+/// single multiplication or division, so both operators are correctly rounded.
+#[derive(Copy, Clone)]
+pub struct F16(u16);
+
+impl F16 {
+    /// Positive infinity.
+    pub const INFINITY: F16 = F16(0x7c00);
+    /// Not a number, specifically the positive quiet NaN without payload.
+    pub const NAN: F16 = F16(0x7e00);
+    /// The largest finite value, 65504.
+    pub const MAX: F16 = F16(0x7bff);
+    /// The smallest positive normal value, 2<sup>-14</sup>.
+    pub const MIN_POSITIVE: F16 = F16(0x0400);
+
+    /// Performs a raw transmutation from an integer.
+    pub const fn from_bits(v: u16) -> F16 {
+        F16(v)
+    }
+
+    /// Performs a raw transmutation to an integer.
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    /// Converts to an `f32`, which is always exact.
+    pub fn to_f32(self) -> f32 {
+        let sign = ((self.0 & 0x8000) as u32) << 16;
+        let exp = (self.0 >> 10) & 0x1f;
+        let sig = (self.0 & 0x3ff) as u32;
+        let abs = match exp {
+            // Multiplying by a power of two is exact, and so is the subnormal significand.
+            0 => sig as f32 * (1.0 / (1 << 24) as f32),
+            0x1f => f32::from_bits(0x7f80_0000 | sig << 13),
+            // Rebias the exponent from 15 to 127.
+            _ => f32::from_bits((exp as u32 + 112) << 23 | sig << 13),
+        };
+        f32::from_bits(abs.to_bits() | sign)
+    }
+
+    /// Rounds an `f32` to the nearest `F16`, with ties to even.
+    ///
+    /// NaNs stay NaNs, keeping the sign, the quiet bit and as much of the payload as fits.
+    pub fn from_f32(x: f32) -> F16 {
+        let bits = x.to_bits();
+        let sign = (bits >> 16) as u16 & 0x8000;
+        let exp = ((bits >> 23) & 0xff) as i32;
+        let sig = bits & 0x7f_ffff;
+        if exp == 0xff {
+            let payload = (sig >> 13) as u16;
+            // Don't let a signalling NaN with a payload only in the low bits become infinity.
+            let nan_bit = (sig != 0 && payload == 0) as u16;
+            return F16(sign | 0x7c00 | payload | nan_bit);
+        }
+        // Rebias the exponent from 127 to 15.
+        let exp = exp - 112;
+        let abs = if exp > 0 {
+            // Rounding up may carry into the exponent, up to and including infinity.
+            let bits = ((exp as u32) << 10 | sig >> 13).min(0x7c00);
+            round_shifted(bits, sig & 0x1fff, 13)
+        } else {
+            // The result is subnormal or zero, including for all `f32` subnormals, whose
+            // hidden bit we can leave out because they round to zero anyway.
+            let sig = if bits & 0x7f80_0000 != 0 { sig | 0x80_0000 } else { sig };
+            let shift = (14 - exp).min(25) as u32;
+            round_shifted(sig >> shift, sig & ((1 << shift) - 1), shift)
+        };
+        F16(sign | abs as u16)
+    }
+}
+
+/// Rounds `truncated`, from which the low `shift` bits `rest` were cut off, to nearest even.
+/// Values too large for `F16` go to infinity by carrying into the exponent.
+fn round_shifted(truncated: u32, rest: u32, shift: u32) -> u32 {
+    let half = 1 << (shift - 1);
+    if truncated >= 0x7c00 {
+        0x7c00
+    } else if rest > half || (rest == half && truncated % 2 == 1) {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
+impl fmt::Debug for F16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_f32(), f)
+    }
+}
+
+impl fmt::LowerExp for F16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerExp::fmt(&self.to_f32(), f)
+    }
+}
+
+impl Neg for F16 {
+    type Output = F16;
+
+    fn neg(self) -> F16 {
+        F16(self.0 ^ 0x8000)
+    }
+}
+
+impl Mul for F16 {
+    type Output = F16;
+
+    fn mul(self, rhs: F16) -> F16 {
+        F16::from_f32(self.to_f32() * rhs.to_f32())
+    }
+}
+
+impl Div for F16 {
+    type Output = F16;
+
+    fn div(self, rhs: F16) -> F16 {
+        F16::from_f32(self.to_f32() / rhs.to_f32())
+    }
+}
+
+/// A bfloat16 number, which keeps the 8 exponent bits of `f32` but only 8 significand bits.
+///
+/// Converting to `f32` just appends zero bits. The arithmetic operators compute in `f32` and
+/// round the result, which is correctly rounded for the same reason as with `F16`.
+#[derive(Copy, Clone)]
+pub struct BF16(u16);
+
+impl BF16 {
+    /// Positive infinity.
+    pub const INFINITY: BF16 = BF16(0x7f80);
+    /// Not a number, specifically the positive quiet NaN without payload.
+    pub const NAN: BF16 = BF16(0x7fc0);
+    /// The largest finite value, about 3.39 * 10<sup>38</sup>.
+    pub const MAX: BF16 = BF16(0x7f7f);
+    /// The smallest positive normal value, 2<sup>-126</sup>.
+    pub const MIN_POSITIVE: BF16 = BF16(0x0080);
+
+    /// Performs a raw transmutation from an integer.
+    pub const fn from_bits(v: u16) -> BF16 {
+        BF16(v)
+    }
+
+    /// Performs a raw transmutation to an integer.
+    pub const fn to_bits(self) -> u16 {
+        self.0
+    }
+
+    /// Converts to an `f32`, which is always exact.
+    pub fn to_f32(self) -> f32 {
+        f32::from_bits((self.0 as u32) << 16)
+    }
+
+    /// Rounds an `f32` to the nearest `BF16`, with ties to even.
+    ///
+    /// NaNs stay NaNs, keeping the sign and the upper bits of the payload, but become quiet.
+    pub fn from_f32(x: f32) -> BF16 {
+        let bits = x.to_bits();
+        if x.is_nan() {
+            return BF16((bits >> 16) as u16 | 0x40);
+        }
+        // The exponents line up, so this rounds subnormals correctly, and rounding up may carry
+        // into the exponent, up to and including infinity.
+        let round = 0x7fff + ((bits >> 16) & 1);
+        BF16(((bits + round) >> 16) as u16)
+    }
+}
+
+impl fmt::Debug for BF16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.to_f32(), f)
+    }
+}
+
+impl fmt::LowerExp for BF16 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::LowerExp::fmt(&self.to_f32(), f)
+    }
+}
+
+impl Neg for BF16 {
+    type Output = BF16;
+
+    fn neg(self) -> BF16 {
+        BF16(self.0 ^ 0x8000)
+    }
+}
+
+impl Mul for BF16 {
+    type Output = BF16;
+
+    fn mul(self, rhs: BF16) -> BF16 {
+        BF16::from_f32(self.to_f32() * rhs.to_f32())
+    }
+}
+
+impl Div for BF16 {
+    type Output = BF16;
+
+    fn div(self, rhs: BF16) -> BF16 {
+        BF16::from_f32(self.to_f32() / rhs.to_f32())
+    }
+}
@@ -0,0 +1,111 @@
+//! Converting hexadecimal floating point strings, such as `0x1.8p3`, into floats.
+//!
+//! Unlike decimal strings, these are binary fractions, so the exact value can always be
+//! represented with enough bits and finding the nearest float is merely a matter of rounding
+//! off the excess bits. No approximations or bignums are required: We keep the leading 64
+//! significant bits and reduce everything past them to a single sticky bit, which is all
+//! half-to-even rounding needs to know.
+use core::cmp::{max, Ordering};
+
+use crate::dec2flt::parse::Decimal;
+use crate::dec2flt::rawfp::{self, RawFloat, Unpacked};
+
+/// Converts the hexadecimal digits and binary exponent of `hex` into the nearest float.
+pub fn convert<T: RawFloat>(hex: Decimal<'_>) -> T {
+    let (m, e, sticky) = leading_bits(&hex);
+    if m == 0 {
+        return T::ZERO;
+    }
+
+    // The place value of the lowest significand bit of the result. Subnormals have less
+    // significand bits than normal numbers, so we're limited by the minimum exponent.
+    let k = max(e + 64 - T::SIG_BITS as i64, T::MIN_EXP_INT as i64);
+    let excess = k - e;
+    if excess > 64 {
+        // Less than half the smallest subnormal.
+        return T::ZERO;
+    }
+
+    let m = m as u128;
+    let mut q = (m >> excess) as u64;
+    let rem = m & ((1 << excess) - 1);
+    let half = 1 << (excess - 1);
+    if rem > half || (rem == half && (sticky || q % 2 == 1)) {
+        q += 1;
+    }
+    let mut k = k;
+    if q > T::MAX_SIG {
+        // Rounding carried into a new bit.
+        q >>= 1;
+        k += 1;
+    }
+    if q < T::MIN_SIG {
+        rawfp::encode_subnormal(q)
+    } else if k > T::MAX_EXP_INT as i64 {
+        T::INFINITY
+    } else {
+        rawfp::encode_normal(Unpacked::new(q, k as i16))
+    }
+}
+
+/// Compares the exact value of `hex` with `m * 2^k`.
+pub fn compare(hex: &Decimal<'_>, m: u64, k: i16) -> Ordering {
+    debug_assert!(m >> 60 == 0);
+    let (x, e, sticky) = leading_bits(hex);
+    // The sticky bits make the value lie strictly between `x` and `x + 1` (in units of `2^e`),
+    // and `m * 2^k` has far fewer significant bits than `x`, so it can't lie in that interval.
+    // Thus `x + 1/2` compares the same way as the exact value.
+    let (x, ex) = ((x as u128) << 1 | sticky as u128, e - 1);
+    let (y, ey) = (m as u128, k as i64);
+    if x == 0 || y == 0 {
+        return x.cmp(&y);
+    }
+    // Compare the place values of the leading bits first, then align the two.
+    let top_x = ex - x.leading_zeros() as i64;
+    let top_y = ey - y.leading_zeros() as i64;
+    if top_x != top_y {
+        return top_x.cmp(&top_y);
+    }
+    if ex >= ey { (x << (ex - ey)).cmp(&y) } else { x.cmp(&(y << (ey - ex))) }
+}
+
+/// Returns the leading 64 significant bits of the value of `hex` as `m * 2^e`, normalized so
+/// that the highest bit of `m` is set (unless `m` is zero), and whether any bits were dropped.
+fn leading_bits(hex: &Decimal<'_>) -> (u64, i64, bool) {
+    // The value is `m * 2^e`, plus something less than `2^e` if `sticky` is set.
+    let mut m: u64 = 0;
+    let mut e = hex.exp;
+    let mut sticky = false;
+    // Leading zeros don't take up any room in `m`, so we keep at least 61 significant bits.
+    // The parser guarantees that anything other than a hexadecimal digit is a digit separator.
+    for &c in hex.integral.iter().filter(|c| c.is_ascii_hexdigit()) {
+        if m >> 60 == 0 {
+            m = m << 4 | hex_digit(c);
+        } else {
+            sticky |= c != b'0';
+            e += 4;
+        }
+    }
+    for &c in hex.fractional.iter().filter(|c| c.is_ascii_hexdigit()) {
+        if m >> 60 == 0 {
+            m = m << 4 | hex_digit(c);
+            e -= 4;
+        } else {
+            sticky |= c != b'0';
+        }
+    }
+    if m == 0 {
+        return (0, 0, false);
+    }
+    // Normalize, so that `m` has exactly 64 significant bits.
+    let shift = m.leading_zeros();
+    (m << shift, e - shift as i64, sticky)
+}
+
+/// Converts an ASCII hexadecimal digit to its value.
+fn hex_digit(c: u8) -> u64 {
+    match c {
+        b'0'..=b'9' => (c - b'0') as u64,
+        _ => ((c | 0x20) - b'a' + 10) as u64,
+    }
+}
@@ -0,0 +1,234 @@
+//! The Eisel-Lemire algorithm.
+//!
+//! For a decimal `w * 10^q` with a 64 bit `w`, this multiplies `w` by a 128 bit approximation of
+//! `5^q` and reads the significand off the high bits of the product. The truncated product is
+//! almost always close enough to decide the rounding, and the rare cases where it isn't are
+//! detected, in which case we fall back to the slower algorithms. It is described in
+//! "Number Parsing at a Gigabyte per Second" by Daniel Lemire,
+//! available online: <https://arxiv.org/abs/2101.11408>
+
+use core::convert::TryInto;
+use crate::dec2flt::num;
+use crate::dec2flt::rawfp::RawFloat;
+use crate::dec2flt::table::{LARGEST_POWER_OF_FIVE, POWER_OF_FIVE_128, SMALLEST_POWER_OF_FIVE};
+
+/// The number of decimal digits that certainly fit into a `u64`.
+const MAX_DIGITS: usize = 19;
+/// The number of decimal digits that certainly fit into a `u128`.
+const MAX_DIGITS_128: usize = 38;
+
+/// Converts the decimal `integral.fractional * 10^e`, which must not have leading zeros.
+///
+/// If there are more than 19 significant digits, the value lies between the first 19 digits
+/// and the next larger number with as many digits. Then we only return a value when both ends
+/// round the same way. Returns `None` when the result can't be decided this way.
+pub fn eisel_lemire<T: RawFloat>(integral: &[u8], fractional: &[u8], e: i64) -> Option<T> {
+    let (w, q, truncated) = leading_digits(integral, fractional, e);
+    let (f, k) = compute_float::<T>(q, w).ok()?;
+    // `w` has at most 19 digits, so `w + 1` can't overflow.
+    if truncated && compute_float::<T>(q, w + 1).ok()? != (f, k) {
+        return None;
+    }
+    Some(encode(f, k))
+}
+
+/// Converts the decimal like `eisel_lemire`, but with up to 38 significant digits in a `u128`,
+/// which are multiplied by the full 128 bit power of five.
+///
+/// The high half of that product is less than one off, so unlike with 64 bits, the result is
+/// certain unless the bits that get rounded away are within that error of a rounding boundary.
+/// This settles many of the inputs with more than 19 digits that `eisel_lemire` can't.
+pub fn eisel_lemire_128<T: RawFloat>(integral: &[u8], fractional: &[u8], e: i64) -> Option<T> {
+    let (w, q, truncated) = leading_digits_128(integral, fractional, e);
+    let (f, k) = compute_float_128::<T>(q, w)?;
+    // `w` has at most 38 digits, so `w + 1` can't overflow.
+    if truncated && compute_float_128::<T>(q, w + 1)? != (f, k) {
+        return None;
+    }
+    Some(encode(f, k))
+}
+
+/// Approximates the decimal `integral.fractional * 10^e` like `eisel_lemire`, but always
+/// returns a result, which is off by at most a few units in the last place when the rounding
+/// couldn't be decided.
+pub fn approximate<T: RawFloat>(integral: &[u8], fractional: &[u8], e: i64) -> T {
+    let (w, q, _) = leading_digits(integral, fractional, e);
+    let (f, k) = compute_float::<T>(q, w).unwrap_or_else(|x| x);
+    encode(f, k)
+}
+
+/// Returns the first 19 digits as an integer `w` and the exponent `q` such that the decimal is
+/// `w * 10^q`, plus whether there were any non-zero digits after the first 19.
+fn leading_digits(integral: &[u8], fractional: &[u8], e: i64) -> (u64, i64, bool) {
+    let mut digits = integral.iter().chain(fractional);
+    let mut w = 0u64;
+    for &d in digits.by_ref().take(MAX_DIGITS) {
+        w = w * 10 + (d - b'0') as u64;
+    }
+    let q = e + (integral.len() + fractional.len()).saturating_sub(MAX_DIGITS) as i64;
+    (w, q, digits.any(|&d| d != b'0'))
+}
+
+/// Like `leading_digits`, but with up to 38 digits in a `u128`.
+fn leading_digits_128(integral: &[u8], fractional: &[u8], e: i64) -> (u128, i64, bool) {
+    let int_len = integral.len().min(MAX_DIGITS_128);
+    let frac_len = fractional.len().min(MAX_DIGITS_128 - int_len);
+    let mut w = 0u128;
+    for digits in [&integral[..int_len], &fractional[..frac_len]].iter() {
+        for chunk in digits.chunks(MAX_DIGITS) {
+            w = w * 10u128.pow(chunk.len() as u32) + num::from_str_unchecked(chunk) as u128;
+        }
+    }
+    let q = e + (integral.len() - int_len + fractional.len() - frac_len) as i64;
+    let mut rest = integral[int_len..].iter().chain(&fractional[frac_len..]);
+    (w, q, rest.any(|&d| d != b'0'))
+}
+
+/// Builds a float from an explicit significand and a biased exponent.
+fn encode<T: RawFloat>(f: u64, k: i32) -> T {
+    let bits = (k as u64) << T::EXPLICIT_SIG_BITS | f;
+    T::from_bits(bits.try_into().unwrap_or_else(|_| unreachable!()))
+}
+
+/// Computes the correctly rounded float closest to `w * 10^q` as an explicit significand and a
+/// biased exponent. If the truncated product isn't precise enough to tell, the error holds the
+/// float that the truncated product rounds to.
+fn compute_float<T: RawFloat>(q: i64, mut w: u64) -> Result<(u64, i32), (u64, i32)> {
+    if w == 0 || q < T::SMALLEST_POWER_OF_TEN as i64 {
+        return Ok((0, 0));
+    } else if q > T::LARGEST_POWER_OF_TEN as i64 {
+        return Ok((0, T::MAX_ENCODED_EXP as i32));
+    }
+    // Normalize `w`, so the product has its most significant bit in one of the top two places.
+    let lz = w.leading_zeros();
+    w <<= lz;
+    let (lo, hi) = compute_product_approx(q, w, T::EXPLICIT_SIG_BITS as u32 + 3);
+    let fp = round_product::<T>(q, lz as i32, lo, hi);
+    // The product is only truncated if `5^q` isn't exact in 128 bits. Then a low half of all
+    // ones means the missing bits could carry into the significand.
+    if lo == !0 && !(-27..=55).contains(&q) {
+        return Err(fp);
+    }
+    // `round_product` doesn't look for ties among subnormals, which need a `w` divisible by
+    // `5^-q` and thus `q >= -27`. Only types with a small exponent range get there.
+    if fp.1 <= 1 && q >= -27 {
+        return Err(fp);
+    }
+    Ok(fp)
+}
+
+/// Computes the float closest to `w * 10^q` like `compute_float`, but from a 128 bit `w`.
+/// Returns `None` when the product isn't precise enough to tell.
+fn compute_float_128<T: RawFloat>(q: i64, w: u128) -> Option<(u64, i32)> {
+    // Unlike with 19 digits, `q` beyond the table doesn't mean zero or infinity yet.
+    if q < SMALLEST_POWER_OF_FIVE as i64 || q > LARGEST_POWER_OF_FIVE as i64 {
+        return None;
+    }
+    let lz = w.leading_zeros();
+    let (hi5, lo5) = POWER_OF_FIVE_128[(q - SMALLEST_POWER_OF_FIVE as i64) as usize];
+    let product = high_multiplication(w << lz, (hi5 as u128) << 64 | lo5 as u128);
+    // The table entry is less than one off, and the normalized `w` is less than `2^128`. So the
+    // exact product lies in `(product - 1, product + 2)`, in units of the last place.
+    // Work out how many bits get rounded away, just like `round_product` does.
+    let (hi, lo) = ((product >> 64) as u64, product as u64);
+    let lz = lz as i32 - 64;
+    let upper_bit = (hi >> 63) as i32;
+    let mut shift = upper_bit + 128 - T::EXPLICIT_SIG_BITS as i32 - 2;
+    let k = power(q as i32) + upper_bit - lz + T::MAX_EXP as i32;
+    if k <= 0 {
+        shift += -k + 1;
+    }
+    // Such tiny values round to zero or the smallest subnormal, which we leave to the slow path.
+    if shift >= 128 {
+        return None;
+    }
+    let rest = product & ((1 << shift) - 1);
+    let half = 1 << (shift - 1);
+    if rest <= 2 || (half - 2..=half + 2).contains(&rest) || rest >= (1 << shift) - 2 {
+        return None;
+    }
+    Some(round_product::<T>(q, lz, lo, hi))
+}
+
+/// Rounds the product `hi:lo` of the normalized `w << lz` and `5^q` to a float.
+fn round_product<T: RawFloat>(q: i64, lz: i32, lo: u64, hi: u64) -> (u64, i32) {
+    let sig_bits = T::EXPLICIT_SIG_BITS as i32;
+    let upper_bit = (hi >> 63) as i32;
+    let mut sig = hi >> (upper_bit + 64 - sig_bits - 3);
+    let mut k = power(q as i32) + upper_bit - lz + T::MAX_EXP as i32;
+    if k <= 0 {
+        // Subnormal or zero. For `f32` and `f64`, these need a very negative `q`, and `w * 10^q`
+        // can't be halfway between two floats then, since that would take a `w` divisible by a
+        // huge power of five. `compute_float` takes care of the other types.
+        if -k + 1 >= 64 {
+            return (0, 0);
+        }
+        sig >>= -k + 1;
+        sig += sig & 1;
+        sig >>= 1;
+        // Rounding up may give the smallest normal number, which has a biased exponent of one.
+        let k = (sig >= 1 << sig_bits) as i32;
+        return (sig, k);
+    }
+    // An exact tie needs `5^q` to be small, and the product to have no bits below those we
+    // keep. Round down to even instead of up in that case.
+    if lo <= 1
+        && q >= T::MIN_EXPONENT_ROUND_TO_EVEN as i64
+        && q <= T::MAX_EXPONENT_ROUND_TO_EVEN as i64
+        && sig & 3 == 1
+        && sig << (upper_bit + 64 - sig_bits - 3) == hi
+    {
+        sig &= !1;
+    }
+    sig += sig & 1;
+    sig >>= 1;
+    if sig >= 2 << sig_bits {
+        sig = 1 << sig_bits;
+        k += 1;
+    }
+    sig &= !(1 << sig_bits);
+    if k >= T::MAX_ENCODED_EXP as i32 {
+        return (0, T::MAX_ENCODED_EXP as i32);
+    }
+    (sig, k)
+}
+
+/// Computes `floor(q * log2(10)) + 63` for `q` in `[-1087, 1087]`, without floating point.
+fn power(q: i32) -> i32 {
+    (q.wrapping_mul(152_170 + 65536) >> 16) + 63
+}
+
+/// Computes the high 128 bits of `w * 5^q`, only taking the low half of the table entry into
+/// account when the bits of interest, the top `precision` bits plus one, may change because
+/// of it. Returns the low and the high word.
+fn compute_product_approx(q: i64, w: u64, precision: u32) -> (u64, u64) {
+    debug_assert!(q >= SMALLEST_POWER_OF_FIVE as i64 && q <= LARGEST_POWER_OF_FIVE as i64);
+    debug_assert!(precision < 64);
+    let mask = !0 >> precision;
+    let (hi5, lo5) = POWER_OF_FIVE_128[(q - SMALLEST_POWER_OF_FIVE as i64) as usize];
+    let (mut lo, mut hi) = full_multiplication(w, hi5);
+    if hi & mask == mask {
+        let (_, carry) = full_multiplication(w, lo5);
+        lo = lo.wrapping_add(carry);
+        if carry > lo {
+            hi += 1;
+        }
+    }
+    (lo, hi)
+}
+
+/// Computes the full 128 bit product of `a` and `b`, as low and high word.
+fn full_multiplication(a: u64, b: u64) -> (u64, u64) {
+    let r = a as u128 * b as u128;
+    (r as u64, (r >> 64) as u64)
+}
+
+/// Computes the high 128 bits of the 256 bit product of `a` and `b`.
+fn high_multiplication(a: u128, b: u128) -> u128 {
+    let (a0, a1) = (a as u64 as u128, a >> 64);
+    let (b0, b1) = (b as u64 as u128, b >> 64);
+    let (low, mid0, mid1, high) = (a0 * b0, a0 * b1, a1 * b0, a1 * b1);
+    // The sum of the middle words and the carry from the low word fits in 66 bits.
+    let mid = (low >> 64) + (mid0 as u64 as u128) + (mid1 as u64 as u128);
+    high + (mid0 >> 64) + (mid1 >> 64) + (mid >> 64)
+}
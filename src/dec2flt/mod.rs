@@ -26,10 +26,14 @@
 //! The `(f, e)` representation is used by almost all code past the parsing stage.
 //!
 //! We then try a long chain of progressively more general and expensive special cases using
-//! machine-sized integers and small, fixed-sized floating point numbers (first `f32`/`f64`, then
-//! a type with 64 bit significand, `Fp`). When all these fail, we bite the bullet and resort to a
-//! simple but very slow algorithm that involved computing `f * 10^e` fully and doing an iterative
-//! search for the best approximation.
+//! machine-sized integers and small, fixed-sized floating point numbers (first `f32`/`f64`, then a
+//! type with 64 bit significand, `Fp`). Right after the `f32`/`f64` fast path, the Eisel-Lemire
+//! algorithm multiplies the leading 19 digits by a 128 bit approximation of a power of five, which
+//! decides almost all remaining inputs with a handful of integer operations. Doing the same with
+//! the leading 38 digits in a `u128` and a 256 bit product settles most of the rest, notably those
+//! with 20 to 38 digits. When all these fail, we bite the bullet and compare `f * 10^e` exactly
+//! with the halfway points around the best approximation we have, which involves bignum
+//! arithmetic.
 //!
 //! Primarily, this module and its children implement the algorithms described in:
 //! "How to Read Floating Point Numbers Accurately" by William D. Clinger,
@@ -39,8 +43,9 @@
 //! in Rust (or at least in core). Our version is additionally complicated by the need to handle
 //! overflow and underflow and the desire to handle subnormal numbers. Bellerophon and
 //! Algorithm R have trouble with overflow, subnormals, and underflow. We conservatively switch to
-//! Algorithm M (with the modifications described in section 8 of the paper) well before the
-//! inputs get into the critical region.
+//! a slow path well before the inputs get into the critical region. Instead of Algorithm M, which
+//! computes the significand by repeated bignum division, the slow path `digit_comp` compares the
+//! input with the exact halfway points around an approximation, as most modern parsers do.
 //!
 //! Another aspect that needs attention is the ``RawFloat`` trait by which almost all functions
 //! are parametrized. One might think that it's enough to parse to `f64` and cast the result to
@@ -53,6 +58,8 @@
 //! we get `0.0150`, which is then rounded up to `0.02`. The same principle applies to other
 //! operations as well, if you want 0.5 ULP accuracy you need to do *everything* in full precision
 //! and round *exactly once, at the end*, by considering all truncated bits at once.
+//! The fast path for `f32`, `F16` and `BF16` is the one exception: it does go through `f64`, but
+//! only for inputs where the second rounding provably cannot change the result.
 //!
 //! FIXME: Although some code duplication is necessary, perhaps parts of the code could be shuffled
 //! around such that less code is duplicated. Large parts of the algorithms are independent of the
@@ -78,14 +85,29 @@
 //! turned into {positive,negative} {zero,infinity}.
 
 use core::fmt;
+use core::num::FpCategory;
 
 use self::num::digits_to_big;
-use self::parse::{parse_decimal, Decimal, ParseResult, Sign};
+use self::parse::{parse_decimal_partial_with_zeros, parse_decimal_with_zeros, parse_hex};
+use self::parse::{parse_json_with_zeros, parse_number_partial_with_zeros, parse_rust_literal};
+use self::parse::{parse_number_with_zeros, Decimal, ParseResult, Sign, ZeroCounts};
 use self::rawfp::RawFloat;
 
+pub use self::fp8::{dec2flt_e4m3fn, dec2flt_e5m2, dec2flt_e8m0};
+pub use self::fp8::{OverflowPolicy, E4M3FN, E5M2, E8M0};
+pub use self::half::{BF16, F16};
+pub use self::strtod::{dec2flt_strtod, StrtodResult};
+pub use self::wide::{dec2flt_f128, dec2flt_x87_extended, F128};
+
 mod algorithm;
+mod fp8;
+mod half;
+mod hex;
+mod lemire;
 mod num;
+mod strtod;
 mod table;
+mod wide;
 // These two have their own tests.
 pub mod parse;
 pub mod rawfp;
@@ -107,20 +129,76 @@ pub mod rawfp;
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParseFloatError {
     kind: FloatErrorKind,
+    index: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-enum FloatErrorKind {
+/// Enum to store the various types of errors that can cause parsing a float to fail.
+///
+/// There is no limit on the number of digits, all of them count towards correct rounding. Values
+/// too large for the target type round to infinity, and values too small to zero, as IEEE 754
+/// prescribes, so neither is an error. Only formats without infinities can run out of range, see
+/// `OutOfRange`.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::{dec2flt, FloatErrorKind};
+///
+/// let e = dec2flt::<f64>("1.5e+").unwrap_err();
+/// assert_eq!(e.kind(), &FloatErrorKind::EmptyExponent);
+/// assert_eq!(e.position(), 5);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum FloatErrorKind {
+    /// Value being parsed is empty.
     Empty,
-    Invalid,
+    /// Contains a character that can't appear at its position, such as the `x` in `1ex`.
+    InvalidDigit,
+    /// The significand has no digits, such as in `-`, `.` or `.e5`.
+    EmptyMantissa,
+    /// The input ends right after the exponent marker or its sign, such as in `1e+`.
+    EmptyExponent,
+    /// A valid number is followed by more characters, such as in `1.5x` or `1..5`.
+    TrailingCharacters,
+    /// A digit separator appears where the separator placement rules don't allow it, such as in
+    /// `1__000` or `1_.5` by default.
+    InvalidDigitSeparator,
+    /// A Rust literal has a suffix other than `f32` or `f64`, such as in `1.5u8`, or it's an
+    /// integer literal without a float suffix, such as `5`.
+    InvalidSuffix,
+    /// A NaN payload doesn't fit into the significand of the target type, or a NaN has a sign
+    /// although the options reject signed NaNs.
+    InvalidNan,
+    /// The value is too large for the finite numbers of the target format, or it has no
+    /// representation at all, such as zero in E8M0, and the `OverflowPolicy` asks for an error.
+    OutOfRange,
 }
 
 impl ParseFloatError {
+    /// Outputs the detailed cause of parsing a float failing.
+    pub fn kind(&self) -> &FloatErrorKind {
+        &self.kind
+    }
+
+    /// Returns the byte offset into the input at which parsing failed: the offending
+    /// character, or the end of the input if more characters were required.
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
     #[doc(hidden)]
     pub fn __description(&self) -> &str {
         match self.kind {
             FloatErrorKind::Empty => "cannot parse float from empty string",
-            FloatErrorKind::Invalid => "invalid float literal",
+            FloatErrorKind::InvalidDigit => "invalid digit found in float literal",
+            FloatErrorKind::EmptyMantissa => "no digits found in float literal",
+            FloatErrorKind::EmptyExponent => "no digits found in float exponent",
+            FloatErrorKind::TrailingCharacters => "trailing characters after float literal",
+            FloatErrorKind::InvalidDigitSeparator => "misplaced digit separator in float literal",
+            FloatErrorKind::InvalidSuffix => "invalid suffix for float literal",
+            FloatErrorKind::InvalidNan => "invalid NaN payload or sign",
+            FloatErrorKind::OutOfRange => "number out of range for float format",
         }
     }
 }
@@ -132,16 +210,16 @@ impl fmt::Display for ParseFloatError {
 }
 
 fn pfe_empty() -> ParseFloatError {
-    ParseFloatError { kind: FloatErrorKind::Empty }
+    ParseFloatError { kind: FloatErrorKind::Empty, index: 0 }
 }
 
-fn pfe_invalid() -> ParseFloatError {
-    ParseFloatError { kind: FloatErrorKind::Invalid }
+fn pfe_invalid(kind: FloatErrorKind, index: usize) -> ParseFloatError {
+    ParseFloatError { kind, index }
 }
 
 /// Splits a decimal string into sign and the rest, without inspecting or validating the rest.
-fn extract_sign(s: &str) -> (Sign, &str) {
-    match s.as_bytes()[0] {
+fn extract_sign(s: &[u8]) -> (Sign, &[u8]) {
+    match s[0] {
         b'+' => (Sign::Positive, &s[1..]),
         b'-' => (Sign::Negative, &s[1..]),
         // If the string is invalid, we never use the sign, so we don't need to validate here.
@@ -149,25 +227,399 @@ fn extract_sign(s: &str) -> (Sign, &str) {
     }
 }
 
+/// Recognizes the special values `inf`, `infinity` and `nan` (in any case) at the start of a
+/// string, and returns the value along with the length of the longest matching name.
+fn parse_inf_nan<T: RawFloat>(s: &[u8]) -> Option<(T, usize)> {
+    let starts_with =
+        |name: &[u8]| matches!(s.get(..name.len()), Some(p) if p.eq_ignore_ascii_case(name));
+    if starts_with(b"nan") {
+        Some((T::NAN, 3))
+    } else if starts_with(b"infinity") {
+        Some((T::INFINITY, 8))
+    } else if starts_with(b"inf") {
+        Some((T::INFINITY, 3))
+    } else {
+        None
+    }
+}
+
+/// Like `parse_inf_nan`, but also recognizes NaN payloads and signalling NaNs if the options
+/// ask for it. Returns an error, with its offset into `s`, if a payload doesn't fit into `T`.
+fn parse_special<T: RawFloat>(
+    s: &[u8],
+    options: &ParseOptions,
+) -> Option<Result<(T, usize), (FloatErrorKind, usize)>> {
+    if !options.nan_payloads {
+        return parse_inf_nan(s).map(Ok);
+    }
+    let (quiet, name_len) = match s.get(..4) {
+        Some(p) if p.eq_ignore_ascii_case(b"snan") => (false, 4),
+        _ => match parse_inf_nan::<T>(s)? {
+            (flt, len) if flt.classify() != FpCategory::Nan => return Some(Ok((flt, len))),
+            (_, len) => (true, len),
+        },
+    };
+    let (payload, payload_len) = parse_nan_payload(&s[name_len..]).unwrap_or((Some(0), 0));
+    let quiet_bit = 1 << (T::EXPLICIT_SIG_BITS - 1);
+    let payload = match payload {
+        Some(payload) if payload < quiet_bit => payload,
+        _ => return Some(Err((FloatErrorKind::InvalidNan, name_len + 1))),
+    };
+    // A signalling NaN needs a non-zero payload. Without one, we set the highest payload bit,
+    // just like C compilers do for `__builtin_nans("")`.
+    let payload = if !quiet && payload == 0 { quiet_bit >> 1 } else { payload };
+    Some(Ok((rawfp::encode_nan(quiet, payload), name_len + payload_len)))
+}
+
+/// Parses a parenthesized NaN payload, a decimal or (with a `0x` prefix) hexadecimal integer,
+/// or nothing at all. Returns the payload, or `None` if it doesn't fit into 64 bits, along with
+/// the length of the whole thing. Returns `None` if there's no payload, or it isn't valid.
+fn parse_nan_payload(s: &[u8]) -> Option<(Option<u64>, usize)> {
+    let s = s.strip_prefix(b"(")?;
+    let end = s.iter().position(|&c| c == b')')?;
+    let (radix, digits) = match &s[..end] {
+        [b'0', b'x' | b'X', digits @ ..] if !digits.is_empty() => (16, digits),
+        digits => (10, digits),
+    };
+    let mut payload: Option<u64> = Some(0);
+    for &c in digits {
+        let digit = (c as char).to_digit(radix)? as u64;
+        payload = payload.and_then(|p| p.checked_mul(radix as u64)?.checked_add(digit));
+    }
+    Some((payload, end + 2))
+}
+
+/// Options to extend the syntax accepted by the parser.
+///
+/// The default options accept exactly what `dec2flt` accepts, and each option enables an
+/// additional syntax on top of that.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::{dec2flt_with_options, ParseOptions};
+///
+/// const OPTIONS: ParseOptions = ParseOptions::new().hex(true);
+/// assert_eq!(dec2flt_with_options::<f64>(b"-0x1.8p3", &OPTIONS), Ok(-12.0));
+/// assert_eq!(dec2flt_with_options::<f64>(b"1.5e1", &OPTIONS), Ok(15.0));
+///
+/// const SEPARATED: ParseOptions = ParseOptions::new().digit_separator(Some(b'_'));
+/// assert_eq!(dec2flt_with_options::<f64>(b"1_000.000_1", &SEPARATED), Ok(1000.0001));
+/// assert!(dec2flt_with_options::<f64>(b"1__000", &SEPARATED).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    hex: bool,
+    digit_separator: Option<u8>,
+    separator_placement: SeparatorPlacement,
+    nan_payloads: bool,
+    reject_signed_nan: bool,
+}
+
+impl ParseOptions {
+    /// Creates the default options.
+    pub const fn new() -> Self {
+        ParseOptions {
+            hex: false,
+            digit_separator: None,
+            separator_placement: SeparatorPlacement::INNER,
+            nan_payloads: false,
+            reject_signed_nan: false,
+        }
+    }
+
+    /// Sets whether hexadecimal floats, such as `0x1.8p3` or `-0x1p-1074`, are accepted.
+    pub const fn hex(mut self, hex: bool) -> Self {
+        self.hex = hex;
+        self
+    }
+
+    /// Sets the character that may separate digits, such as `b'_'` in `1_000.000_1`, or `None`
+    /// to not accept any. Separators may appear in the integral, fractional and exponent digits,
+    /// where `separator_placement` allows them, and don't count towards any digit limits.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the separator is a letter, a digit or the decimal point.
+    pub const fn digit_separator(mut self, separator: Option<u8>) -> Self {
+        if let Some(c) = separator {
+            assert!(!c.is_ascii_alphanumeric() && c != b'.', "invalid digit separator");
+        }
+        self.digit_separator = separator;
+        self
+    }
+
+    /// Sets where digit separators may appear within a run of digits.
+    pub const fn separator_placement(mut self, placement: SeparatorPlacement) -> Self {
+        self.separator_placement = placement;
+        self
+    }
+
+    /// Sets whether NaN payloads and signalling NaNs are accepted, such as `nan(1234)`,
+    /// `nan(0x4d2)`, `snan` or `snan(0x1)`, in any case. The payload goes into the significand
+    /// bits below the quiet bit, and it's an error if it doesn't fit there.
+    pub const fn nan_payloads(mut self, nan_payloads: bool) -> Self {
+        self.nan_payloads = nan_payloads;
+        self
+    }
+
+    /// Sets whether NaNs may have a sign, as in `-nan`, which sets the sign bit of the NaN.
+    pub const fn signed_nan(mut self, signed_nan: bool) -> Self {
+        self.reject_signed_nan = !signed_nan;
+        self
+    }
+}
+
+/// Rules for where digit separators may appear within a run of digits.
+///
+/// A separator between two digits is always allowed. Everything else must be enabled
+/// explicitly, and the rules apply to the integral, fractional and exponent digits alike.
+/// A separator is never a digit by itself, so the runs of `_.5` or `1e_` are empty.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::{dec2flt_with_options, ParseOptions, SeparatorPlacement};
+///
+/// const OPTIONS: ParseOptions = ParseOptions::new()
+///     .digit_separator(Some(b'_'))
+///     .separator_placement(SeparatorPlacement::RUST);
+/// assert_eq!(dec2flt_with_options::<f64>(b"1__000_.5_", &OPTIONS), Ok(1000.5));
+/// assert!(dec2flt_with_options::<f64>(b"1._5", &OPTIONS).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SeparatorPlacement {
+    leading: bool,
+    trailing: bool,
+    consecutive: bool,
+}
+
+impl SeparatorPlacement {
+    /// Separators only between two digits and never two in a row, as in Python (`1_000.5`)
+    /// and C++14 (`1'000.5`). Also the default.
+    pub const INNER: SeparatorPlacement =
+        SeparatorPlacement { leading: false, trailing: false, consecutive: false };
+
+    /// Trailing and consecutive separators, as in Rust literals (`1__000_.5`). Note that Rust
+    /// additionally accepts a separator right after the exponent marker (`1e_5`), which these
+    /// rules don't. Use `dec2flt_rust_literal` for the exact grammar of Rust literals.
+    pub const RUST: SeparatorPlacement =
+        SeparatorPlacement { leading: false, trailing: true, consecutive: true };
+
+    /// Creates the default rules, `INNER`.
+    pub const fn new() -> Self {
+        Self::INNER
+    }
+
+    /// Sets whether separators may appear before the first digit of a run, such as in `_1`,
+    /// `1._5` or `1e_5`.
+    pub const fn leading(mut self, leading: bool) -> Self {
+        self.leading = leading;
+        self
+    }
+
+    /// Sets whether separators may appear after the last digit of a run, such as in `1_`,
+    /// `1_.5` or `1e5_`.
+    pub const fn trailing(mut self, trailing: bool) -> Self {
+        self.trailing = trailing;
+        self
+    }
+
+    /// Sets whether several separators may appear in a row, such as in `1__000`.
+    pub const fn consecutive(mut self, consecutive: bool) -> Self {
+        self.consecutive = consecutive;
+        self
+    }
+}
+
 /// Converts a decimal string into a floating point number.
 pub fn dec2flt<T: RawFloat>(s: &str) -> Result<T, ParseFloatError> {
+    dec2flt_bytes(s.as_bytes())
+}
+
+/// Converts a decimal byte string into a floating point number.
+///
+/// The parser only ever looks at ASCII characters, so this skips the UTF-8 validation that
+/// getting a `&str` would take, and otherwise behaves exactly like `dec2flt`. Any non-ASCII
+/// byte is simply an invalid character.
+pub fn dec2flt_bytes<T: RawFloat>(s: &[u8]) -> Result<T, ParseFloatError> {
+    dec2flt_with(s, &ParseOptions::new(), parse_decimal_with_zeros)
+}
+
+/// Converts a byte string into a floating point number, accepting the syntax given by `options`.
+pub fn dec2flt_with_options<T: RawFloat>(
+    s: &[u8],
+    options: &ParseOptions,
+) -> Result<T, ParseFloatError> {
+    dec2flt_with(s, options, |s| parse_number_with_zeros(s, options))
+}
+
+/// Converts a hexadecimal float, as printed by C's `%a`, into the nearest floating point number.
+///
+/// The input must have a `0x` or `0X` prefix (after the optional sign), the binary exponent is
+/// optional. Excess bits are rounded off with the half-to-even strategy, just like the excess
+/// digits of decimal strings.
+pub fn dec2flt_hex<T: RawFloat>(s: &[u8]) -> Result<T, ParseFloatError> {
+    dec2flt_with(s, &ParseOptions::new(), |s| (parse_hex(s), ZeroCounts::default()))
+}
+
+/// Converts a number in the strict syntax of JSON (RFC 8259) into a floating point number.
+///
+/// Unlike `dec2flt_bytes`, this rejects everything JSON doesn't allow: a leading `+`, a point
+/// without digits on both sides (`.5`, `5.`), leading zeros (`007`), and the special values
+/// `inf` and `nan`. The error position points at the offending character. Numbers too large
+/// for `T` are not an error and convert to infinity, the same as in `dec2flt_bytes`.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::{dec2flt_json, FloatErrorKind};
+///
+/// assert_eq!(dec2flt_json::<f64>(b"-0.5e+2"), Ok(-50.0));
+/// let e = dec2flt_json::<f64>(b"007").unwrap_err();
+/// assert_eq!((e.kind(), e.position()), (&FloatErrorKind::InvalidDigit, 1));
+/// ```
+pub fn dec2flt_json<T: RawFloat>(s: &[u8]) -> Result<T, ParseFloatError> {
+    let (sign, rest) = match s {
+        [] => return Err(pfe_empty()),
+        [b'-', rest @ ..] => (Sign::Negative, rest),
+        _ => (Sign::Positive, s),
+    };
+    let sign_len = s.len() - rest.len();
+    let (result, zeros) = parse_json_with_zeros(rest);
+    let flt = match result {
+        ParseResult::Valid(decimal) => convert(decimal, zeros),
+        ParseResult::Hexadecimal(hex) => hex::convert(hex),
+        ParseResult::ShortcutToInf => T::INFINITY,
+        ParseResult::ShortcutToZero => T::ZERO,
+        ParseResult::Invalid(kind, index) => return Err(pfe_invalid(kind, sign_len + index)),
+    };
+
+    match sign {
+        Sign::Positive => Ok(flt),
+        Sign::Negative => Ok(-flt),
+    }
+}
+
+/// The type suffix of a Rust float literal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FloatSuffix {
+    /// `f32`, as in `1.5f32`.
+    F32,
+    /// `f64`, as in `1.5f64`.
+    F64,
+}
+
+/// The value of a Rust float literal, in the type its suffix asks for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RustFloat {
+    F32(f32),
+    F64(f64),
+}
+
+/// Converts a Rust float literal, such as `1_000.5f32` or `2e10_f64`, to the type given by its
+/// suffix, and returns the suffix along with the value. Literals without a suffix are `f64`,
+/// just like rustc defaults to.
+///
+/// The literal must follow the grammar rustc accepts for float literals, see
+/// `parse::parse_rust_literal`, with no sign and no surrounding whitespace. Underscores may
+/// appear anywhere after the first digit of the integral and fractional parts, and anywhere
+/// in the exponent. Integer literals are accepted only with a float suffix (`5f32`), while
+/// `1.` is a float literal but `1.e5` and `.5` are not. Literals too large for their type are
+/// converted to infinity, rejecting them is up to the `overflowing_literals` lint.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::{dec2flt_rust_literal, FloatSuffix, RustFloat};
+///
+/// let (value, suffix) = dec2flt_rust_literal(b"1_000.5f32").unwrap();
+/// assert_eq!((value, suffix), (RustFloat::F32(1000.5), Some(FloatSuffix::F32)));
+/// assert_eq!(dec2flt_rust_literal(b"0.1E-3").unwrap(), (RustFloat::F64(0.1E-3), None));
+/// assert!(dec2flt_rust_literal(b"1.e5").is_err());
+/// ```
+pub fn dec2flt_rust_literal(
+    s: &[u8],
+) -> Result<(RustFloat, Option<FloatSuffix>), ParseFloatError> {
+    let (result, suffix) = parse_rust_literal(s);
+    let flt = match suffix {
+        Some(FloatSuffix::F32) => RustFloat::F32(convert_rust_literal(s, result)?),
+        Some(FloatSuffix::F64) | None => RustFloat::F64(convert_rust_literal(s, result)?),
+    };
+    Ok((flt, suffix))
+}
+
+/// Converts a Rust float literal to `T`, regardless of its suffix, and returns the suffix
+/// along with the value. Otherwise, this is the same as `dec2flt_rust_literal`.
+pub fn dec2flt_rust_literal_as<T: RawFloat>(
+    s: &[u8],
+) -> Result<(T, Option<FloatSuffix>), ParseFloatError> {
+    let (result, suffix) = parse_rust_literal(s);
+    Ok((convert_rust_literal(s, result)?, suffix))
+}
+
+/// Converts the result of `parse_rust_literal` for the literal `s`.
+fn convert_rust_literal<T: RawFloat>(
+    s: &[u8],
+    result: ParseResult<'_>,
+) -> Result<T, ParseFloatError> {
+    if s.is_empty() {
+        return Err(pfe_empty());
+    }
+    match result {
+        // The digits may still contain underscores.
+        ParseResult::Valid(decimal) => Ok(convert_separated(&decimal)),
+        ParseResult::Hexadecimal(hex) => Ok(hex::convert(hex)),
+        ParseResult::ShortcutToInf => Ok(T::INFINITY),
+        ParseResult::ShortcutToZero => Ok(T::ZERO),
+        ParseResult::Invalid(kind, index) => Err(pfe_invalid(kind, index)),
+    }
+}
+
+/// Converts the longest prefix of a decimal byte string that is a valid floating point number,
+/// and returns it along with the number of bytes it spans. Like `strtod`, parsing stops at the
+/// first character that can't continue the number, so `"1.5e+x"` consumes the three bytes of
+/// `1.5`. This is the partial counterpart of `dec2flt_bytes`.
+///
+/// Returns an error if the string doesn't start with a number (or `inf`/`nan`) at all.
+pub fn dec2flt_partial<T: RawFloat>(s: &[u8]) -> Result<(T, usize), ParseFloatError> {
+    dec2flt_partial_with(s, &ParseOptions::new(), parse_decimal_partial_with_zeros)
+}
+
+/// The partial counterpart of `dec2flt_with_options`.
+pub fn dec2flt_partial_with_options<T: RawFloat>(
+    s: &[u8],
+    options: &ParseOptions,
+) -> Result<(T, usize), ParseFloatError> {
+    dec2flt_partial_with(s, options, |s| parse_number_partial_with_zeros(s, options))
+}
+
+/// Drives the conversion of a complete string, using `parse` to decompose the unsigned number
+/// according to `options`.
+fn dec2flt_with<T, F>(s: &[u8], options: &ParseOptions, parse: F) -> Result<T, ParseFloatError>
+where
+    T: RawFloat,
+    F: FnOnce(&[u8]) -> (ParseResult<'_>, ZeroCounts),
+{
     if s.is_empty() {
         return Err(pfe_empty());
     }
-    let (sign, s) = extract_sign(s);
-    let flt = match parse_decimal(s) {
-        ParseResult::Valid(decimal) => convert(decimal)?,
+    let (sign, rest) = extract_sign(s);
+    let sign_len = s.len() - rest.len();
+    let (result, zeros) = parse(rest);
+    let flt = match result {
+        ParseResult::Valid(decimal) => convert_with_options(decimal, zeros, options),
+        ParseResult::Hexadecimal(hex) => hex::convert(hex),
         ParseResult::ShortcutToInf => T::INFINITY,
         ParseResult::ShortcutToZero => T::ZERO,
-        ParseResult::Invalid => {
-            if s.eq_ignore_ascii_case("nan") {
-                T::NAN
-            } else if s.eq_ignore_ascii_case("inf") || s.eq_ignore_ascii_case("infinity") {
-                T::INFINITY
-            } else {
-                return Err(pfe_invalid());
+        ParseResult::Invalid(kind, index) => match parse_special(rest, options) {
+            Some(Ok((flt, len))) if len == rest.len() => check_nan_sign(flt, sign_len, options)?,
+            Some(Ok((_, len))) => {
+                return Err(pfe_invalid(FloatErrorKind::TrailingCharacters, sign_len + len));
             }
-        }
+            Some(Err((kind, index))) => return Err(pfe_invalid(kind, sign_len + index)),
+            None => return Err(pfe_invalid(kind, sign_len + index)),
+        },
     };
 
     match sign {
@@ -176,25 +628,163 @@ pub fn dec2flt<T: RawFloat>(s: &str) -> Result<T, ParseFloatError> {
     }
 }
 
+/// Rejects NaNs with a sign if the options ask for it.
+fn check_nan_sign<T: RawFloat>(
+    flt: T,
+    sign_len: usize,
+    options: &ParseOptions,
+) -> Result<T, ParseFloatError> {
+    if options.reject_signed_nan && sign_len != 0 && flt.classify() == FpCategory::Nan {
+        return Err(pfe_invalid(FloatErrorKind::InvalidNan, 0));
+    }
+    Ok(flt)
+}
+
+/// Drives the conversion of a string prefix, using `parse` to decompose the unsigned number
+/// according to `options`.
+fn dec2flt_partial_with<T, F>(
+    s: &[u8],
+    options: &ParseOptions,
+    parse: F,
+) -> Result<(T, usize), ParseFloatError>
+where
+    T: RawFloat,
+    F: FnOnce(&[u8]) -> (ParseResult<'_>, usize, ZeroCounts),
+{
+    if s.is_empty() {
+        return Err(pfe_empty());
+    }
+    let (sign, rest) = extract_sign(s);
+    let sign_len = s.len() - rest.len();
+    let (result, len, zeros) = parse(rest);
+    let (flt, len) = match result {
+        ParseResult::Valid(decimal) => (convert_with_options(decimal, zeros, options), len),
+        ParseResult::Hexadecimal(hex) => (hex::convert(hex), len),
+        ParseResult::ShortcutToInf => (T::INFINITY, len),
+        ParseResult::ShortcutToZero => (T::ZERO, len),
+        ParseResult::Invalid(kind, index) => match parse_special(rest, options) {
+            Some(Ok((flt, len))) => (check_nan_sign(flt, sign_len, options)?, len),
+            Some(Err((kind, index))) => return Err(pfe_invalid(kind, sign_len + index)),
+            None => return Err(pfe_invalid(kind, sign_len + index)),
+        },
+    };
+
+    match sign {
+        Sign::Positive => Ok((flt, sign_len + len)),
+        Sign::Negative => Ok((-flt, sign_len + len)),
+    }
+}
+
+/// The maximum number of significant decimal digits that take part in the conversion.
+///
+/// A decimal value exactly halfway between two adjacent `f64` (or `f32`) values has at most 767
+/// significant digits. Once that many digits are known, the remaining ones can only tell whether
+/// the input lies exactly on the truncated value or strictly above it, never on which side of a
+/// halfway point it falls. See `truncate()`.
+const MAX_SIG_DIGITS: usize = 768;
+
+/// Converts a decimal that was parsed according to `options`, which may leave digit separators
+/// in its digits.
+fn convert_with_options<T: RawFloat>(
+    decimal: Decimal<'_>,
+    zeros: ZeroCounts,
+    options: &ParseOptions,
+) -> T {
+    match options.digit_separator {
+        None => convert(decimal, zeros),
+        Some(_) => convert_separated(&decimal),
+    }
+}
+
+/// Converts a decimal with digit separators in its digits, whose zeros the parser didn't count.
+fn convert_separated<T: RawFloat>(decimal: &Decimal<'_>) -> T {
+    let mut buffer = [0; MAX_SIG_DIGITS + 1];
+    let decimal = compact_digits(decimal, &mut buffer);
+    let zeros = ZeroCounts::count(&decimal);
+    convert(decimal, zeros)
+}
+
+/// Copies the significant digits of `decimal` into `buffer`, leaving out digit separators and
+/// leading zeros. Like `truncate()`, only as many digits as fit into `buffer` minus one are
+/// kept, with a single `1` digit appended if any of the dropped digits is non-zero.
+fn compact_digits<'a>(decimal: &Decimal<'_>, buffer: &'a mut [u8]) -> Decimal<'a> {
+    let max_digits = buffer.len() - 1;
+    let mut len = 0;
+    let mut exp = decimal.exp;
+    let mut truncated = false;
+    // The parser guarantees that anything other than a digit is a separator.
+    for &d in decimal.integral.iter().filter(|d| d.is_ascii_digit()) {
+        if len == 0 && d == b'0' {
+            continue;
+        }
+        if len < max_digits {
+            buffer[len] = d;
+            len += 1;
+        } else {
+            truncated |= d != b'0';
+            exp += 1;
+        }
+    }
+    let integral_len = len;
+    for &d in decimal.fractional.iter().filter(|d| d.is_ascii_digit()) {
+        if len == 0 && d == b'0' {
+            exp -= 1;
+            continue;
+        }
+        if len < max_digits {
+            buffer[len] = d;
+            len += 1;
+        } else {
+            truncated |= d != b'0';
+        }
+    }
+    if truncated {
+        // Behind all kept digits, even if the integral part was cut short: The value then lies
+        // strictly between the kept digits and the next number in the last kept place.
+        buffer[len] = b'1';
+        len += 1;
+    }
+    let (integral, fractional) = buffer[..len].split_at(integral_len);
+    Decimal::new(integral, fractional, exp)
+}
+
 /// The main workhorse for the decimal-to-float conversion: Orchestrate all the preprocessing
 /// and figure out which algorithm should do the actual conversion.
-fn convert<T: RawFloat>(mut decimal: Decimal<'_>) -> Result<T, ParseFloatError> {
-    simplify(&mut decimal);
+///
+/// `zeros` are the zeros at either end of the digits, as counted by the parser.
+fn convert<T: RawFloat>(mut decimal: Decimal<'_>, zeros: ZeroCounts) -> T {
+    simplify(&mut decimal, zeros);
     if let Some(x) = trivial_cases(&decimal) {
-        return Ok(x);
+        return x;
     }
     // Remove/shift out the decimal point.
     let e = decimal.exp - decimal.fractional.len() as i64;
     if let Some(x) = algorithm::fast_path(decimal.integral, decimal.fractional, e) {
-        return Ok(x);
+        return x;
+    }
+    if let Some(x) = lemire::eisel_lemire(decimal.integral, decimal.fractional, e) {
+        return x;
     }
-    // Big32x40 is limited to 1280 bits, which translates to about 385 decimal digits.
-    // If we exceed this, we'll crash, so we error out before getting too close (within 10^10).
-    let upper_bound = bound_intermediate_digits(&decimal, e);
-    if upper_bound > 375 {
-        return Err(pfe_invalid());
+    if let Some(x) = lemire::eisel_lemire_128(decimal.integral, decimal.fractional, e) {
+        return x;
     }
-    let f = digits_to_big(decimal.integral, decimal.fractional);
+    let truncated = truncate(&mut decimal);
+    let mut e = decimal.exp - decimal.fractional.len() as i64;
+    let mut f_len = decimal.integral.len() + decimal.fractional.len();
+    let mut f = digits_to_big(decimal.integral, decimal.fractional);
+    if truncated {
+        // Some non-zero digits were cut off, so the real value lies strictly between `f` and
+        // `f + 1` (in units of the last kept digit). Appending a single `1` digit lands in the
+        // same open interval, which contains no halfway point, so it rounds the same way.
+        f.mul_small(10).add_small(1);
+        e -= 1;
+        f_len += 1;
+    }
+    // `Big` is limited to 6400 bits, which translates to about 1926 decimal digits.
+    // With at most `MAX_SIG_DIGITS + 1` digits and the exponent limits of `trivial_cases`,
+    // the bound never exceeds 1881 digits, which leaves the same safety margin as before.
+    let upper_bound = bound_intermediate_digits(f_len as u64, e);
+    debug_assert!(upper_bound <= 1881);
 
     // Now the exponent certainly fits in 16 bit, which is used throughout the main algorithms.
     let e = e as i16;
@@ -203,9 +793,11 @@ fn convert<T: RawFloat>(mut decimal: Decimal<'_>) -> Result<T, ParseFloatError>
     let exponent_in_range = table::MIN_E <= e && e <= table::MAX_E;
     let value_in_range = upper_bound <= T::MAX_NORMAL_DIGITS as u64;
     if exponent_in_range && value_in_range {
-        Ok(algorithm::bellerophon(&f, e))
+        algorithm::bellerophon(&f, e)
     } else {
-        Ok(algorithm::algorithm_m(&f, e))
+        let e_approx = decimal.exp - decimal.fractional.len() as i64;
+        let z = lemire::approximate(decimal.integral, decimal.fractional, e_approx);
+        algorithm::digit_comp(&f, e, z)
     }
 }
 
@@ -213,47 +805,70 @@ fn convert<T: RawFloat>(mut decimal: Decimal<'_>) -> Result<T, ParseFloatError>
 // `inline(always)` is a workaround for that. There are only two call sites overall and it doesn't
 // make code size worse.
 
-/// Strip zeros where possible, even when this requires changing the exponent
+/// Strip zeros where possible, even when this requires changing the exponent. The zeros were
+/// already counted along with the digits, so this doesn't need to look at the digits again.
 #[inline(always)]
-fn simplify(decimal: &mut Decimal<'_>) {
-    let is_zero = &|&&d: &&u8| -> bool { d == b'0' };
+fn simplify(decimal: &mut Decimal<'_>, zeros: ZeroCounts) {
     // Trimming these zeros does not change anything but may enable the fast path (< 15 digits).
-    let leading_zeros = decimal.integral.iter().take_while(is_zero).count();
-    decimal.integral = &decimal.integral[leading_zeros..];
-    let trailing_zeros = decimal.fractional.iter().rev().take_while(is_zero).count();
-    let end = decimal.fractional.len() - trailing_zeros;
+    decimal.integral = &decimal.integral[zeros.integral.leading..];
+    let end = decimal.fractional.len() - zeros.fractional.trailing;
     decimal.fractional = &decimal.fractional[..end];
     // Simplify numbers of the form 0.0...x and x...0.0, adjusting the exponent accordingly.
     // This may not always be a win (possibly pushes some numbers out of the fast path), but it
     // simplifies other parts significantly (notably, approximating the magnitude of the value).
     if decimal.integral.is_empty() {
-        let leading_zeros = decimal.fractional.iter().take_while(is_zero).count();
+        // If all of the digits are zeros, the trailing ones above were all of them.
+        let leading_zeros = zeros.fractional.leading.min(decimal.fractional.len());
         decimal.fractional = &decimal.fractional[leading_zeros..];
         decimal.exp -= leading_zeros as i64;
     } else if decimal.fractional.is_empty() {
-        let trailing_zeros = decimal.integral.iter().rev().take_while(is_zero).count();
+        // There is a non-zero digit, so stripping the leading zeros left the trailing ones alone.
+        let trailing_zeros = zeros.integral.trailing;
         let end = decimal.integral.len() - trailing_zeros;
         decimal.integral = &decimal.integral[..end];
         decimal.exp += trailing_zeros as i64;
     }
 }
 
+/// Drops all but the first `MAX_SIG_DIGITS` significant digits, adjusting the exponent for
+/// digits cut off the integral part. Returns `true` if any of the dropped digits is non-zero.
+///
+/// Must be called after `simplify()`, so that there are no leading zeros left to count.
+fn truncate(decimal: &mut Decimal<'_>) -> bool {
+    let is_nonzero = |&d: &u8| d != b'0';
+    let integral_len = decimal.integral.len();
+    if integral_len + decimal.fractional.len() <= MAX_SIG_DIGITS {
+        return false;
+    }
+    if integral_len >= MAX_SIG_DIGITS {
+        let dropped = &decimal.integral[MAX_SIG_DIGITS..];
+        let truncated = dropped.iter().any(is_nonzero) || decimal.fractional.iter().any(is_nonzero);
+        decimal.exp += dropped.len() as i64;
+        decimal.integral = &decimal.integral[..MAX_SIG_DIGITS];
+        decimal.fractional = b"";
+        truncated
+    } else {
+        let (kept, dropped) = decimal.fractional.split_at(MAX_SIG_DIGITS - integral_len);
+        decimal.fractional = kept;
+        dropped.iter().any(is_nonzero)
+    }
+}
+
 /// Returns a quick-an-dirty upper bound on the size (log10) of the largest value that Algorithm R
-/// and Algorithm M will compute while working on the given decimal.
-fn bound_intermediate_digits(decimal: &Decimal<'_>, e: i64) -> u64 {
+/// and the digit comparison will compute while working on `f_len` decimal digits scaled by `10^e`.
+fn bound_intermediate_digits(f_len: u64, e: i64) -> u64 {
     // We don't need to worry too much about overflow here thanks to trivial_cases() and the
     // parser, which filter out the most extreme inputs for us.
-    let f_len: u64 = decimal.integral.len() as u64 + decimal.fractional.len() as u64;
     if e >= 0 {
         // In the case e >= 0, both algorithms compute about `f * 10^e`. Algorithm R proceeds to
         // do some complicated calculations with this but we can ignore that for the upper bound
         // because it also reduces the fraction beforehand, so we have plenty of buffer there.
         f_len + (e as u64)
     } else {
-        // If e < 0, Algorithm R does roughly the same thing, but Algorithm M differs:
-        // It tries to find a positive number k such that `f << k / 10^e` is an in-range
-        // significand. This will result in about `2^53 * f * 10^e` < `10^17 * f * 10^e`.
-        // One input that triggers this is 0.33...33 (375 x 3).
+        // If e < 0, both compare `f` with `m * 10^abs(e)`, times some power of two, where the
+        // significand `m` (or twice that, plus one, for a halfway point) is less than `2^55`.
+        // This results in about `10^17 * 10^abs(e)`, and `f` may be larger by a power of two.
+        // One input that triggers this is 0.33...33 (768 x 3).
         f_len + e.unsigned_abs() + 17
     }
 }
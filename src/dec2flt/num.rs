@@ -3,8 +3,19 @@
 // FIXME This module's name is a bit unfortunate, since other modules also import `core::num`.
 
 use core::cmp::Ordering::{self, Equal, Greater, Less};
+use core::convert::TryInto;
 
-pub use crate::bignum::Big32x40 as Big;
+// 64 bit digits need far fewer operations, but only if `u128` arithmetic compiles to a few
+// instructions, as it does on 64 bit targets.
+#[cfg(not(target_pointer_width = "64"))]
+pub use crate::bignum::{Big32x200 as Big, Digit32 as Digit};
+#[cfg(target_pointer_width = "64")]
+pub use crate::bignum::{Big64x100 as Big, Digit64 as Digit};
+
+/// The number of decimal digits that certainly fit into a `Digit`, that is, the largest `k`
+/// with `10^k < 2^BITS`. Since `log10(2)` is a little over 0.3, this rounds down correctly
+/// for all digit sizes we use.
+pub const DECIMAL_DIGITS_PER_DIGIT: usize = Digit::BITS as usize * 3 / 10;
 
 /// Test whether truncating all bits less significant than `ones_place` introduces
 /// a relative error less, equal, or greater than 0.5 ULP.
@@ -36,37 +47,210 @@ pub fn compare_with_half_ulp(f: &Big, ones_place: usize) -> Ordering {
 /// 1. using `FromStr` on `&[u8]` requires `from_utf8_unchecked`, which is bad, and
 /// 2. piecing together the results of `integral.parse()` and `fractional.parse()` is
 ///    more complicated than this entire function.
-pub fn from_str_unchecked<'a, T>(bytes: T) -> u64
-where
-    T: IntoIterator<Item = &'a u8>,
-{
-    let mut result = 0;
+pub fn from_str_unchecked(bytes: &[u8]) -> u64 {
+    append_digits(0, bytes)
+}
+
+/// Appends the ASCII digits in `bytes` to the decimal digits of `result`, that is, computes
+/// `result * 10^len + bytes`. This is `from_str_unchecked` for digits split across several
+/// strings and has the same caveats.
+pub fn append_digits(mut result: u64, mut bytes: &[u8]) -> u64 {
+    while bytes.len() >= 8 {
+        let (chunk, rest) = bytes.split_at(8);
+        result = result * 100_000_000 + parse_8_digits(load_8(chunk));
+        bytes = rest;
+    }
     for &c in bytes {
         result = result * 10 + (c - b'0') as u64;
     }
     result
 }
 
+/// Converts eight ASCII digits, loaded as a little endian `u64`, to their value.
+///
+/// Instead of eight multiply-adds, this combines neighbouring digits into pairs, the pairs into
+/// groups of four and the groups into the result, each step for all lanes at once ("SWAR").
+fn parse_8_digits(mut v: u64) -> u64 {
+    const MASK: u64 = 0x0000_00ff_0000_00ff;
+    const MUL1: u64 = 100 + (1_000_000 << 32);
+    const MUL2: u64 = 1 + (10_000 << 32);
+    v -= 0x3030_3030_3030_3030;
+    // Every other byte now holds the value of two neighbouring digits.
+    v = v * 10 + (v >> 8);
+    // Pick two of these pairs each, scale them by their place value and sum everything up in
+    // the upper half of the product.
+    ((v & MASK).wrapping_mul(MUL1) + ((v >> 16) & MASK).wrapping_mul(MUL2)) >> 32
+}
+
+/// The zeros at the start and at the end of a run of ASCII digits. In a run of nothing but
+/// zeros, both are its length.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ZeroRuns {
+    pub leading: usize,
+    pub trailing: usize,
+}
+
+impl ZeroRuns {
+    /// Counts the zeros at either end of the ASCII digits `digits`.
+    pub fn count(digits: &[u8]) -> ZeroRuns {
+        scan_digit_run(digits).1
+    }
+}
+
+/// Where the non-zero digits of a run start and end, as far as it has been scanned.
+struct NonZeroSpan {
+    start: usize,
+    end: usize,
+}
+
+impl NonZeroSpan {
+    const EMPTY: NonZeroSpan = NonZeroSpan { start: usize::MAX, end: 0 };
+
+    /// Extends the span to the non-zero digits from `first` to `last`, inclusive, which come
+    /// after all the ones seen so far.
+    fn extend(&mut self, first: usize, last: usize) {
+        self.start = self.start.min(first);
+        self.end = last + 1;
+    }
+
+    fn zero_runs(&self, len: usize) -> ZeroRuns {
+        ZeroRuns { leading: self.start.min(len), trailing: len - self.end }
+    }
+}
+
+/// Returns the length of the run of ASCII digits at the start of `s`, along with the zeros at
+/// either end of that run.
+///
+/// This looks at eight bytes at a time, or sixteen where SIMD instructions are available, which
+/// makes a difference for the long runs of digits in full precision numbers. The zeros are
+/// counted in the same pass, so `simplify` doesn't need to look at the digits again.
+// Short runs are the common case, and a call costs more than scanning them.
+#[inline(always)]
+pub fn scan_digit_run(s: &[u8]) -> (usize, ZeroRuns) {
+    let mut non_zero = NonZeroSpan::EMPTY;
+    let mut len = simd::digit_blocks_len(s, &mut non_zero);
+    while let Some(chunk) = s.get(len..len + 8) {
+        let v = load_8(chunk);
+        let mask = non_digit_mask(v);
+        // Only the bytes before the first non-digit are part of the run.
+        let digits = if mask == 0 { !0 } else { (mask & mask.wrapping_neg()) - 1 };
+        let non_zeros = non_zero_mask(v) & digits;
+        if non_zeros != 0 {
+            let first = len + (non_zeros.trailing_zeros() / 8) as usize;
+            let last = len + 7 - (non_zeros.leading_zeros() / 8) as usize;
+            non_zero.extend(first, last);
+        }
+        if mask != 0 {
+            let len = len + (mask.trailing_zeros() / 8) as usize;
+            return (len, non_zero.zero_runs(len));
+        }
+        len += 8;
+    }
+    for &c in &s[len..] {
+        if !c.is_ascii_digit() {
+            break;
+        }
+        if c != b'0' {
+            non_zero.extend(len, len);
+        }
+        len += 1;
+    }
+    (len, non_zero.zero_runs(len))
+}
+
+/// Loads eight bytes such that the first one ends up in the least significant byte.
+fn load_8(chunk: &[u8]) -> u64 {
+    u64::from_le_bytes(chunk.try_into().unwrap())
+}
+
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+
+/// Sets the high bit of those bytes of `v` that aren't ASCII digits, and clears all other bits.
+fn non_digit_mask(v: u64) -> u64 {
+    // Without their high bits, the bytes can't carry or borrow into each other below.
+    let low = v & !HIGH_BITS;
+    let above_nine = low + 0x4646_4646_4646_4646;
+    let below_zero = !((low | HIGH_BITS) - 0x3030_3030_3030_3030);
+    (v | above_nine | below_zero) & HIGH_BITS
+}
+
+/// Sets the high bit of those bytes of `v` that aren't `b'0'`, and clears all other bits.
+/// Only the bytes up to the first one that isn't an ASCII digit get the right bit.
+fn non_zero_mask(v: u64) -> u64 {
+    // Each digit byte now holds its value, which is small enough to not carry. Other bytes may
+    // carry, but only into the bytes after them, or out of the top.
+    let values = v ^ 0x3030_3030_3030_3030;
+    values.wrapping_add(!HIGH_BITS) & HIGH_BITS
+}
+
+// SSE2 compares sixteen bytes at once, and is always available on x86-64.
+#[cfg(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2"))]
+mod simd {
+    #[cfg(target_arch = "x86")]
+    use core::arch::x86::*;
+    #[cfg(target_arch = "x86_64")]
+    use core::arch::x86_64::*;
+
+    use super::NonZeroSpan;
+
+    /// Returns the length of the blocks of sixteen digits at the start of `s`, and adds their
+    /// non-zero digits to `non_zero`.
+    pub fn digit_blocks_len(s: &[u8], non_zero: &mut NonZeroSpan) -> usize {
+        let mut len = 0;
+        while let Some(block) = s.get(len..len + 16) {
+            // SAFETY: SSE2 is enabled, and the unaligned load reads exactly the bytes of `block`.
+            let (digits, zeros) = unsafe {
+                let v = _mm_loadu_si128(block.as_ptr() as *const __m128i);
+                let above = _mm_cmpgt_epi8(v, _mm_set1_epi8(b'0' as i8 - 1));
+                let below = _mm_cmplt_epi8(v, _mm_set1_epi8(b'9' as i8 + 1));
+                let zeros = _mm_cmpeq_epi8(v, _mm_set1_epi8(b'0' as i8));
+                (_mm_movemask_epi8(_mm_and_si128(above, below)), _mm_movemask_epi8(zeros))
+            };
+            if digits != 0xffff {
+                break;
+            }
+            let non_zeros = !zeros & 0xffff;
+            if non_zeros != 0 {
+                let first = len + non_zeros.trailing_zeros() as usize;
+                let last = len + 31 - non_zeros.leading_zeros() as usize;
+                non_zero.extend(first, last);
+            }
+            len += 16;
+        }
+        len
+    }
+}
+
+// Everywhere else, we leave all of the work to the portable version.
+#[cfg(not(all(any(target_arch = "x86", target_arch = "x86_64"), target_feature = "sse2")))]
+mod simd {
+    use super::NonZeroSpan;
+
+    pub fn digit_blocks_len(_: &[u8], _: &mut NonZeroSpan) -> usize {
+        0
+    }
+}
+
 /// Converts a string of ASCII digits into a bignum.
 ///
 /// Like `from_str_unchecked`, this function relies on the parser to weed out non-digits.
+/// The digits are consumed in runs that fit into a single `Digit`, each of which takes one
+/// fused multiply-add pass over the bignum, rather than one per decimal digit.
 pub fn digits_to_big(integral: &[u8], fractional: &[u8]) -> Big {
     let mut f = Big::from_small(0);
-    for &c in integral.iter().chain(fractional) {
-        let n = (c - b'0') as u32;
-        f.mul_small(10);
-        f.add_small(n);
-    }
+    let mut append = |mut digits: &[u8]| {
+        while !digits.is_empty() {
+            let (run, rest) = digits.split_at(DECIMAL_DIGITS_PER_DIGIT.min(digits.len()));
+            let scale = (10 as Digit).pow(run.len() as u32);
+            f.mul_small_add(scale, from_str_unchecked(run) as Digit);
+            digits = rest;
+        }
+    };
+    append(integral);
+    append(fractional);
     f
 }
 
-/// Unwraps a bignum into a 64 bit integer. Panics if the number is too large.
-pub fn to_u64(x: &Big) -> u64 {
-    assert!(x.bit_length() < 64);
-    let d = x.digits();
-    if d.len() < 2 { d[0] as u64 } else { (d[1] as u64) << 32 | d[0] as u64 }
-}
-
 /// Extracts a range of bits.
 
 /// Index 0 is the least significant bit and the range is half-open as usual.
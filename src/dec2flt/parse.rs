@@ -5,13 +5,21 @@
 //! In other words, standard floating-point syntax, with two exceptions: No sign, and no
 //! handling of "inf" and "NaN". These are handled by the driver function (super::dec2flt).
 //!
+//! Hexadecimal floats (`0x1.8p3`) are decomposed the same way when enabled, with hexadecimal
+//! digits and a `'p' | 'P'` marker introducing a (decimal) binary exponent. When a digit
+//! separator is enabled, it may also appear within each of the digit runs, and is left in the
+//! integral and fractional parts for the caller to skip.
+//!
 //! Although recognizing valid inputs is relatively easy, this module also has to reject the
 //! countless invalid variations, never panic, and perform numerous checks that the other
 //! modules rely on to not panic (or overflow) in turn.
 //! To make matters worse, all that happens in a single pass over the input.
 //! So, be careful when modifying anything, and double-check with the other modules.
-use self::ParseResult::{Invalid, ShortcutToInf, ShortcutToZero, Valid};
-use super::num;
+use self::ParseResult::{Hexadecimal, Invalid, ShortcutToInf, ShortcutToZero, Valid};
+use super::num::{self, ZeroRuns};
+use super::FloatErrorKind::{self, EmptyExponent, EmptyMantissa, InvalidDigit};
+use super::FloatErrorKind::{InvalidDigitSeparator, InvalidSuffix, TrailingCharacters};
+use super::{FloatSuffix, ParseOptions, SeparatorPlacement};
 
 #[derive(Debug)]
 pub enum Sign {
@@ -19,7 +27,7 @@ pub enum Sign {
     Negative,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 /// The interesting parts of a decimal string.
 pub struct Decimal<'a> {
     pub integral: &'a [u8],
@@ -34,88 +42,438 @@ impl<'a> Decimal<'a> {
     }
 }
 
+/// The zeros at either end of the integral and fractional digits of a valid decimal. The parser
+/// counts them in passing, so that the conversion can strip them without looking at the digits
+/// again. See `eat_digit_run` for when they aren't counted.
+#[derive(Debug, Clone, Copy, Default)]
+pub(super) struct ZeroCounts {
+    pub integral: ZeroRuns,
+    pub fractional: ZeroRuns,
+}
+
+impl ZeroCounts {
+    /// Counts the zeros of digits that don't come straight from the parser.
+    pub(super) fn count(decimal: &Decimal<'_>) -> ZeroCounts {
+        let integral = ZeroRuns::count(decimal.integral);
+        ZeroCounts { integral, fractional: ZeroRuns::count(decimal.fractional) }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ParseResult<'a> {
     Valid(Decimal<'a>),
+    /// A hexadecimal float: the digits are hexadecimal and the exponent is a power of two.
+    Hexadecimal(Decimal<'a>),
     ShortcutToInf,
     ShortcutToZero,
-    Invalid,
+    /// Why and where (as a byte offset into the input) parsing failed.
+    Invalid(FloatErrorKind, usize),
 }
 
 /// Checks if the input string is a valid floating point number and if so, locate the integral
 /// part, the fractional part, and the exponent in it. Does not handle signs.
-pub fn parse_decimal(s: &str) -> ParseResult<'_> {
-    if s.is_empty() {
-        return Invalid;
+pub fn parse_decimal(s: &[u8]) -> ParseResult<'_> {
+    parse_decimal_with_zeros(s).0
+}
+
+/// Like `parse_decimal`, but also returns the zeros at either end of the digits.
+pub(super) fn parse_decimal_with_zeros(s: &[u8]) -> (ParseResult<'_>, ZeroCounts) {
+    parse_float(s, false, &ParseOptions::new())
+}
+
+/// Like `parse_decimal`, but only parses the longest prefix of the input that is a valid floating
+/// point number, and also returns the length of that prefix. If there is no such prefix, the
+/// result is `Invalid` and the length zero.
+pub fn parse_decimal_partial(s: &[u8]) -> (ParseResult<'_>, usize) {
+    let (result, len, _) = parse_decimal_partial_with_zeros(s);
+    (result, len)
+}
+
+/// Like `parse_decimal_partial`, but also returns the zeros at either end of the digits.
+pub(super) fn parse_decimal_partial_with_zeros(s: &[u8]) -> (ParseResult<'_>, usize, ZeroCounts) {
+    parse_float_partial(s, false, &ParseOptions::new())
+}
+
+/// Checks if the input string is a valid hexadecimal floating point number, that is, `0x` or
+/// `0X` followed by the same syntax as `parse_decimal` with hexadecimal digits and a `p` or `P`
+/// exponent marker. Does not handle signs.
+pub fn parse_hex(s: &[u8]) -> ParseResult<'_> {
+    parse_hex_with(s, &ParseOptions::new())
+}
+
+fn parse_hex_with<'a>(s: &'a [u8], options: &ParseOptions) -> ParseResult<'a> {
+    match s {
+        [b'0', b'x' | b'X', body @ ..] => match parse_float(body, true, options).0 {
+            Invalid(kind, index) => Invalid(kind, index + 2),
+            result => result,
+        },
+        [] | [b'0'] => Invalid(EmptyMantissa, s.len()),
+        [b'0', ..] => Invalid(InvalidDigit, 1),
+        _ => Invalid(InvalidDigit, 0),
     }
+}
 
-    let s = s.as_bytes();
-    let (integral, s) = eat_digits(s);
+/// The partial counterpart of `parse_hex`. An incomplete hexadecimal prefix (`0x` followed by
+/// no digits) is not valid, callers are free to parse the leading `0` as a decimal instead.
+pub fn parse_hex_partial(s: &[u8]) -> (ParseResult<'_>, usize) {
+    parse_hex_partial_with(s, &ParseOptions::new())
+}
 
-    match s.first() {
-        None => Valid(Decimal::new(integral, b"", 0)),
-        Some(&b'e' | &b'E') => {
-            if integral.is_empty() {
-                return Invalid; // No digits before 'e'
-            }
+fn parse_hex_partial_with<'a>(s: &'a [u8], options: &ParseOptions) -> (ParseResult<'a>, usize) {
+    match s {
+        [b'0', b'x' | b'X', body @ ..] => match parse_float_partial(body, true, options) {
+            (Invalid(kind, index), _, _) => (Invalid(kind, index + 2), 0),
+            (result, len, _) => (result, len + 2),
+        },
+        _ => (parse_hex(s), 0),
+    }
+}
+
+/// Checks if the input string is a number as defined by the JSON grammar (RFC 8259), minus the
+/// sign: `int frac? exp?`, where `int` is `0` or a digit string without leading zeros and `frac`
+/// is a point followed by at least one digit. Beyond that, the grammar is the same as that of
+/// `parse_decimal`, which does the remaining work.
+pub fn parse_json(s: &[u8]) -> ParseResult<'_> {
+    parse_json_with_zeros(s).0
+}
+
+/// Like `parse_json`, but also returns the zeros at either end of the digits.
+pub(super) fn parse_json_with_zeros(s: &[u8]) -> (ParseResult<'_>, ZeroCounts) {
+    let (int_len, _) = num::scan_digit_run(s);
+    let invalid = |kind, index| (Invalid(kind, index), ZeroCounts::default());
+    match s.split_at(int_len) {
+        ([], []) => invalid(EmptyMantissa, 0),
+        ([], _) => invalid(InvalidDigit, 0),
+        ([b'0', _, ..], _) => invalid(InvalidDigit, 1),
+        (_, [b'.']) => invalid(EmptyMantissa, s.len()),
+        (_, [b'.', c, ..]) if !c.is_ascii_digit() => invalid(InvalidDigit, int_len + 1),
+        _ => parse_decimal_with_zeros(s),
+    }
+}
 
-            parse_exp(integral, b"", &s[1..])
+/// Checks if the input string is a Rust float literal, and if so, decomposes it and returns the
+/// type suffix along with it. The grammar is that of rustc:
+///
+/// `digits ('.' | '.' digits suffix? | ('.' digits)? ('e' | 'E') ('+' | '-')? digits suffix?)`
+///
+/// where `digits` starts with a digit and may contain underscores after that, except in the
+/// exponent, where underscores may also come first. Integer literals (`digits`) with a float
+/// suffix are float literals as well. The suffix must be `f32` or `f64`, and there's no sign.
+/// Underscores are left in the integral and fractional parts for the caller to skip.
+pub fn parse_rust_literal(s: &[u8]) -> (ParseResult<'_>, Option<FloatSuffix>) {
+    const MANTISSA: ParseOptions = ParseOptions::new()
+        .digit_separator(Some(b'_'))
+        .separator_placement(SeparatorPlacement::RUST);
+    const EXPONENT: ParseOptions =
+        MANTISSA.separator_placement(SeparatorPlacement::RUST.leading(true));
+    match parse_rust_literal_unchecked(s, &MANTISSA, &EXPONENT) {
+        (Invalid(kind, index), _) => (check_separator(Invalid(kind, index), s, &MANTISSA), None),
+        result => result,
+    }
+}
+
+fn parse_rust_literal_unchecked<'a>(
+    s: &'a [u8],
+    mantissa_options: &ParseOptions,
+    exp_options: &ParseOptions,
+) -> (ParseResult<'a>, Option<FloatSuffix>) {
+    // Binary, octal and hexadecimal integer literals can't have a float suffix.
+    if let [b'0', b'b' | b'o' | b'x', ..] = s {
+        return (Invalid(InvalidDigit, 1), None);
+    }
+    let (integral, rest, _) = eat_digit_run(s, u8::is_ascii_digit, mantissa_options);
+    if integral.is_empty() {
+        return (Invalid(if s.is_empty() { EmptyMantissa } else { InvalidDigit }, 0), None);
+    }
+    let is_integer = rest.first() != Some(&b'.');
+    let (fractional, rest) = match rest {
+        [b'.', c, ..] if c.is_ascii_digit() => {
+            let (fractional, rest, _) =
+                eat_digit_run(&rest[1..], u8::is_ascii_digit, mantissa_options);
+            (fractional, rest)
         }
-        Some(&b'.') => {
-            let (fractional, s) = eat_digits(&s[1..]);
-            if integral.is_empty() && fractional.is_empty() {
-                // We require at least a single digit before or after the point.
-                return Invalid;
-            }
+        // `1.` is a complete literal. What follows can't be part of it: `1.e5` and `1.f32`
+        // access fields, `1._5` too, and `1..5` is a range.
+        [b'.'] => return (Valid(Decimal::new(integral, b"", 0)), None),
+        [b'.', ..] => return (Invalid(TrailingCharacters, integral.len() + 1), None),
+        _ => (&b""[..], rest),
+    };
+    let decimal = Decimal::new(integral, fractional, 0);
+    let exp_start = s.len() - rest.len() + 1;
+    let (result, rest, has_exp) = match rest.first() {
+        Some(b'e' | b'E') => match parse_exp(decimal, &rest[1..], false, exp_options) {
+            Ok((result, len)) => (result, &rest[1 + len..], true),
+            Err((kind, index)) => return (Invalid(kind, exp_start + index), None),
+        },
+        _ => (Valid(decimal), rest, false),
+    };
+    let suffix_start = s.len() - rest.len();
+    let suffix = match rest {
+        b"f32" => Some(FloatSuffix::F32),
+        b"f64" => Some(FloatSuffix::F64),
+        [] if is_integer && !has_exp => return (Invalid(InvalidSuffix, s.len()), None),
+        [] => None,
+        [c, ..] if c.is_ascii_alphabetic() => return (Invalid(InvalidSuffix, suffix_start), None),
+        _ => return (Invalid(TrailingCharacters, suffix_start), None),
+    };
+    (result, suffix)
+}
+
+/// Parses a number in any of the syntaxes enabled by `options`.
+pub fn parse_number<'a>(s: &'a [u8], options: &ParseOptions) -> ParseResult<'a> {
+    parse_number_with_zeros(s, options).0
+}
 
-            match s.first() {
-                None => Valid(Decimal::new(integral, fractional, 0)),
-                Some(&b'e' | &b'E') => parse_exp(integral, fractional, &s[1..]),
-                _ => Invalid, // Trailing junk after fractional part
+/// Like `parse_number`, but also returns the zeros at either end of the digits.
+pub(super) fn parse_number_with_zeros<'a>(
+    s: &'a [u8],
+    options: &ParseOptions,
+) -> (ParseResult<'a>, ZeroCounts) {
+    let (result, zeros) = if options.hex && has_hex_prefix(s) {
+        (parse_hex_with(s, options), ZeroCounts::default())
+    } else {
+        parse_float(s, false, options)
+    };
+    (check_separator(result, s, options), zeros)
+}
+
+/// The partial counterpart of `parse_number`.
+pub fn parse_number_partial<'a>(s: &'a [u8], options: &ParseOptions) -> (ParseResult<'a>, usize) {
+    let (result, len, _) = parse_number_partial_with_zeros(s, options);
+    (result, len)
+}
+
+/// Like `parse_number_partial`, but also returns the zeros at either end of the digits.
+pub(super) fn parse_number_partial_with_zeros<'a>(
+    s: &'a [u8],
+    options: &ParseOptions,
+) -> (ParseResult<'a>, usize, ZeroCounts) {
+    if options.hex && has_hex_prefix(s) {
+        match parse_hex_partial_with(s, options) {
+            (Invalid(..), _) => {}
+            (result, len) => return (result, len, ZeroCounts::default()),
+        }
+    }
+    let (result, len, zeros) = parse_float_partial(s, false, options);
+    (check_separator(result, s, options), len, zeros)
+}
+
+/// Blames misplaced digit separators for the error if there is one at the error position.
+fn check_separator<'a>(
+    result: ParseResult<'a>,
+    s: &[u8],
+    options: &ParseOptions,
+) -> ParseResult<'a> {
+    match (result, options.digit_separator) {
+        (Invalid(InvalidDigit | TrailingCharacters, index), Some(separator))
+            if s.get(index) == Some(&separator) =>
+        {
+            Invalid(InvalidDigitSeparator, index)
+        }
+        (result, _) => result,
+    }
+}
+
+fn has_hex_prefix(s: &[u8]) -> bool {
+    matches!(s, [b'0', b'x' | b'X', ..])
+}
+
+/// Decomposes a decimal or (without the `0x` prefix) hexadecimal float spanning all of `s`.
+fn parse_float<'a>(
+    s: &'a [u8],
+    hex: bool,
+    options: &ParseOptions,
+) -> (ParseResult<'a>, ZeroCounts) {
+    let (mantissa, rest, zeros) = match parse_mantissa(s, hex, options) {
+        Ok(parts) => parts,
+        Err((kind, index)) => return (Invalid(kind, index), ZeroCounts::default()),
+    };
+    let exp_start = s.len() - rest.len() + 1;
+
+    let result = match rest.first() {
+        None => valid(mantissa, hex),
+        Some(&c) if is_exp_marker(c, hex) => match parse_exp(mantissa, &rest[1..], hex, options) {
+            Ok((result, len)) if exp_start + len == s.len() => result,
+            Ok((_, len)) => Invalid(TrailingCharacters, exp_start + len),
+            Err((kind, index)) => Invalid(kind, exp_start + index),
+        },
+        _ => Invalid(TrailingCharacters, s.len() - rest.len()),
+    };
+    (result, zeros)
+}
+
+/// Decomposes the longest prefix of `s` that is a decimal or (without the `0x` prefix)
+/// hexadecimal float.
+fn parse_float_partial<'a>(
+    s: &'a [u8],
+    hex: bool,
+    options: &ParseOptions,
+) -> (ParseResult<'a>, usize, ZeroCounts) {
+    let (mantissa, rest, zeros) = match parse_mantissa(s, hex, options) {
+        Ok(parts) => parts,
+        Err((kind, index)) => return (Invalid(kind, index), 0, ZeroCounts::default()),
+    };
+    let len = s.len() - rest.len();
+
+    let (result, len) = match rest.first() {
+        Some(&c) if is_exp_marker(c, hex) => {
+            let (integral, fractional) = (mantissa.integral, mantissa.fractional);
+            match parse_exp(mantissa, &rest[1..], hex, options) {
+                Ok((result, exp_len)) => (result, len + 1 + exp_len),
+                // An incomplete exponent is not part of the number.
+                Err(_) => (valid(Decimal::new(integral, fractional, 0), hex), len),
             }
         }
-        _ => Invalid, // Trailing junk after first digit string
+        _ => (valid(mantissa, hex), len),
+    };
+    (result, len, zeros)
+}
+
+fn valid(decimal: Decimal<'_>, hex: bool) -> ParseResult<'_> {
+    if hex { Hexadecimal(decimal) } else { Valid(decimal) }
+}
+
+fn is_exp_marker(c: u8, hex: bool) -> bool {
+    if hex { c == b'p' || c == b'P' } else { c == b'e' || c == b'E' }
+}
+
+/// Splits off the integral and fractional digits, returning them (with a zero exponent) along
+/// with the unparsed rest and their zeros. At least one of the two must be non-empty.
+// Inlined for the same reason as `eat_digit_run`, whose results would otherwise take a detour
+// through memory.
+#[inline(always)]
+fn parse_mantissa<'a>(
+    s: &'a [u8],
+    hex: bool,
+    options: &ParseOptions,
+) -> Result<(Decimal<'a>, &'a [u8], ZeroCounts), (FloatErrorKind, usize)> {
+    let is_digit = if hex { u8::is_ascii_hexdigit } else { u8::is_ascii_digit };
+    let (integral, rest, integral_zeros) = eat_digit_run(s, is_digit, options);
+    let (fractional, rest, fractional_zeros) = match rest.first() {
+        Some(&b'.') => eat_digit_run(&rest[1..], is_digit, options),
+        _ => (&b""[..], rest, ZeroRuns::default()),
+    };
+    if integral.is_empty() && fractional.is_empty() {
+        // We require at least a single digit before or after the point.
+        let index = s.len() - rest.len();
+        return match rest.first() {
+            Some(&c) if !is_exp_marker(c, hex) => Err((InvalidDigit, index)),
+            _ => Err((EmptyMantissa, index)),
+        };
     }
+    let zeros = ZeroCounts { integral: integral_zeros, fractional: fractional_zeros };
+    Ok((Decimal::new(integral, fractional, 0), rest, zeros))
 }
 
-/// Carves off decimal digits up to the first non-digit character.
-fn eat_digits(s: &[u8]) -> (&[u8], &[u8]) {
-    let pos = s.iter().position(|c| !c.is_ascii_digit()).unwrap_or(s.len());
-    s.split_at(pos)
+/// Carves off the longest run of digits, as recognized by `is_digit`, that is valid according
+/// to the digit separator rules of `options`. Without a separator, this is simply everything up
+/// to the first non-digit character. Returns the run, the rest, and the zeros at either end of
+/// the run.
+///
+/// A run consisting of nothing but separators is empty, separators always go with digits.
+///
+/// The zeros are only counted for decimal digits without separators. Hexadecimal digits and
+/// runs with separators are compacted by their conversions first, which skips the zeros anyway,
+/// so both counts are zero for them.
+// Like `simplify`, this needs to be inlined into its few callers. Otherwise the fast path for
+// runs without separators takes a call, which is a lot for short numbers.
+#[inline(always)]
+fn eat_digit_run<'a>(
+    s: &'a [u8],
+    is_digit: fn(&u8) -> bool,
+    options: &ParseOptions,
+) -> (&'a [u8], &'a [u8], ZeroRuns) {
+    let separator = match options.digit_separator {
+        Some(separator) => separator,
+        // Only hexadecimal digits include letters, decimal runs can take the faster path.
+        None if !is_digit(&b'a') => {
+            let (len, zeros) = num::scan_digit_run(s);
+            let (run, rest) = s.split_at(len);
+            return (run, rest, zeros);
+        }
+        None => {
+            let pos = s.iter().position(|c| !is_digit(c)).unwrap_or(s.len());
+            let (run, rest) = s.split_at(pos);
+            return (run, rest, ZeroRuns::default());
+        }
+    };
+    let placement = &options.separator_placement;
+    // The end of the longest valid run seen so far.
+    let mut end = 0;
+    let mut seen_digit = false;
+    let mut after_separator = false;
+    for (i, c) in s.iter().enumerate() {
+        if is_digit(c) {
+            seen_digit = true;
+            after_separator = false;
+            end = i + 1;
+        } else if *c == separator {
+            if (!seen_digit && !placement.leading) || (after_separator && !placement.consecutive)
+            {
+                break;
+            }
+            after_separator = true;
+            if seen_digit && placement.trailing {
+                end = i + 1;
+            }
+        } else {
+            break;
+        }
+    }
+    let (run, rest) = s.split_at(end);
+    (run, rest, ZeroRuns::default())
 }
 
-/// Exponent extraction and error checking.
-fn parse_exp<'a>(integral: &'a [u8], fractional: &'a [u8], rest: &'a [u8]) -> ParseResult<'a> {
-    let (sign, rest) = match rest.first() {
+/// Exponent extraction and error checking. Returns the result along with the number of bytes
+/// of `rest` that belong to the exponent, trailing characters are left for the caller.
+/// Errors are reported with an offset into `rest`.
+fn parse_exp<'a>(
+    mut decimal: Decimal<'a>,
+    rest: &[u8],
+    hex: bool,
+    options: &ParseOptions,
+) -> Result<(ParseResult<'a>, usize), (FloatErrorKind, usize)> {
+    let (sign, digits) = match rest.first() {
         Some(&b'-') => (Sign::Negative, &rest[1..]),
         Some(&b'+') => (Sign::Positive, &rest[1..]),
         _ => (Sign::Positive, rest),
     };
-    let (mut number, trailing) = eat_digits(rest);
-    if !trailing.is_empty() {
-        return Invalid; // Trailing junk after exponent
-    }
+    let (mut number, trailing, _) = eat_digit_run(digits, u8::is_ascii_digit, options);
+    let len = rest.len() - trailing.len();
     if number.is_empty() {
-        return Invalid; // Empty exponent
+        return match trailing.first() {
+            None => Err((EmptyExponent, len)),
+            Some(_) => Err((InvalidDigit, len)),
+        };
     }
     // At this point, we certainly have a valid string of digits. It may be too long to put into
     // an `i64`, but if it's that huge, the input is certainly zero or infinity. Since each zero
     // in the decimal digits only adjusts the exponent by +/- 1, at exp = 10^18 the input would
     // have to be 17 exabyte (!) of zeros to get even remotely close to being finite.
     // This is not exactly a use case we need to cater to.
-    while number.first() == Some(&b'0') {
-        number = &number[1..];
+    // Separators are skipped here, so they don't count towards that limit.
+    while let Some((&c, tail)) = number.split_first() {
+        if c.is_ascii_digit() && c != b'0' {
+            break;
+        }
+        number = tail;
     }
-    if number.len() >= 18 {
+    let number_len = number.iter().filter(|c| c.is_ascii_digit()).count();
+    if number_len >= 18 {
+        // Unless all the digits are zero, of course: Zero times anything is still zero.
+        let mut digits = decimal.integral.iter().chain(decimal.fractional);
+        if digits.all(|&d| !d.is_ascii_alphanumeric() || d == b'0') {
+            return Ok((valid(decimal, hex), len));
+        }
         return match sign {
-            Sign::Positive => ShortcutToInf,
-            Sign::Negative => ShortcutToZero,
+            Sign::Positive => Ok((ShortcutToInf, len)),
+            Sign::Negative => Ok((ShortcutToZero, len)),
         };
     }
-    let abs_exp = num::from_str_unchecked(number);
-    let e = match sign {
+    let abs_exp = number.split(|c| !c.is_ascii_digit()).fold(0, num::append_digits);
+    decimal.exp = match sign {
         Sign::Positive => abs_exp as i64,
         Sign::Negative => -(abs_exp as i64),
     };
-    Valid(Decimal::new(integral, fractional, e))
+    Ok((valid(decimal, hex), len))
 }
@@ -15,17 +15,18 @@
 //! We call (1) the **fractional representation** and (2) the **integral representation**.
 //!
 //! Many functions in this module only handle normal numbers. The dec2flt routines conservatively
-//! take the universally-correct slow path (Algorithm M) for very small and very large numbers.
-//! That algorithm needs only next_float() which does handle subnormals and zeros.
+//! take the universally-correct slow path for very small and very large numbers. That algorithm
+//! needs only next_float() and prev_float(), which do handle subnormals and infinity.
 use core::cmp::Ordering::{Equal, Greater, Less};
 use core::convert::{TryFrom, TryInto};
 use core::fmt::{Debug, LowerExp};
+use crate::dec2flt::half::{BF16, F16};
 use crate::dec2flt::num::{self, Big};
 use crate::dec2flt::table;
 use crate::diy_float::Fp;
 use core::num::FpCategory;
 use core::num::FpCategory::{Infinite, Nan, Normal, Subnormal, Zero};
-use core::ops::{Add, Div, Mul, Neg};
+use core::ops::{Add, Div, Mul, Neg, Sub};
 
 #[derive(Copy, Clone, Debug)]
 pub struct Unpacked {
@@ -39,7 +40,8 @@ impl Unpacked {
     }
 }
 
-/// A helper trait to avoid duplicating basically all the conversion code for `f32` and `f64`.
+/// A helper trait to avoid duplicating basically all the conversion code for `f32`, `f64`, `F16`
+/// and `BF16`.
 ///
 /// See the parent module's doc comment for why this is necessary.
 ///
@@ -52,7 +54,7 @@ pub trait RawFloat:
     const ZERO: Self;
 
     /// Type used by `to_bits` and `from_bits`.
-    type Bits: Add<Output = Self::Bits> + From<u8> + TryFrom<u64>;
+    type Bits: Add<Output = Self::Bits> + Sub<Output = Self::Bits> + From<u8> + TryFrom<u64>;
 
     /// Performs a raw transmutation to an integer.
     fn to_bits(self) -> Self::Bits;
@@ -77,6 +79,10 @@ pub trait RawFloat:
     /// Panics for `e >= CEIL_LOG5_OF_MAX_SIG`.
     fn short_fast_pow10(e: usize) -> Self;
 
+    /// Whether the arithmetic operators are hardware instructions that round exactly once. The
+    /// fast path only computes in this type if they are.
+    const NATIVE_ARITHMETIC: bool;
+
     /// What the name says. It's easier to hard code than juggling intrinsics and
     /// hoping LLVM constant folds it.
     const CEIL_LOG5_OF_MAX_SIG: i16;
@@ -93,6 +99,20 @@ pub trait RawFloat:
     /// is certainly rounded to zero.
     const ZERO_CUTOFF: i64;
 
+    /// The smallest decimal exponent for which a product `w * 5^q` with a 64 bit `w` can be
+    /// exactly halfway between two floats. Used to detect ties in the Eisel-Lemire algorithm.
+    const MIN_EXPONENT_ROUND_TO_EVEN: i32;
+
+    /// The largest decimal exponent for which a product `w * 5^q` with a 64 bit `w` can be
+    /// exactly halfway between two floats.
+    const MAX_EXPONENT_ROUND_TO_EVEN: i32;
+
+    /// Any `w * 10^q` with a 64 bit `w` is certainly rounded to zero if `q` is less than this.
+    const SMALLEST_POWER_OF_TEN: i32;
+
+    /// Any non-zero `w * 10^q` is certainly rounded to infinity if `q` is greater than this.
+    const LARGEST_POWER_OF_TEN: i32;
+
     /// The number of bits in the exponent.
     const EXP_BITS: u8;
 
@@ -138,7 +158,6 @@ macro_rules! other_constants {
 
         const INFINITY: Self = $type::INFINITY;
         const NAN: Self = $type::NAN;
-        const ZERO: Self = 0.0;
     };
 }
 
@@ -147,10 +166,16 @@ impl RawFloat for f32 {
 
     const SIG_BITS: u8 = 24;
     const EXP_BITS: u8 = 8;
+    const NATIVE_ARITHMETIC: bool = true;
     const CEIL_LOG5_OF_MAX_SIG: i16 = 11;
     const MAX_NORMAL_DIGITS: usize = 35;
     const INF_CUTOFF: i64 = 40;
     const ZERO_CUTOFF: i64 = -48;
+    const MIN_EXPONENT_ROUND_TO_EVEN: i32 = -17;
+    const MAX_EXPONENT_ROUND_TO_EVEN: i32 = 10;
+    const SMALLEST_POWER_OF_TEN: i32 = -65;
+    const LARGEST_POWER_OF_TEN: i32 = 38;
+    const ZERO: Self = 0.0;
     other_constants!(f32);
 
     /// Returns the mantissa, exponent and sign as integers.
@@ -196,10 +221,16 @@ impl RawFloat for f64 {
 
     const SIG_BITS: u8 = 53;
     const EXP_BITS: u8 = 11;
+    const NATIVE_ARITHMETIC: bool = true;
     const CEIL_LOG5_OF_MAX_SIG: i16 = 23;
     const MAX_NORMAL_DIGITS: usize = 305;
     const INF_CUTOFF: i64 = 310;
     const ZERO_CUTOFF: i64 = -326;
+    const MIN_EXPONENT_ROUND_TO_EVEN: i32 = -4;
+    const MAX_EXPONENT_ROUND_TO_EVEN: i32 = 23;
+    const SMALLEST_POWER_OF_TEN: i32 = -342;
+    const LARGEST_POWER_OF_TEN: i32 = 308;
+    const ZERO: Self = 0.0;
     other_constants!(f64);
 
     /// Returns the mantissa, exponent and sign as integers.
@@ -243,6 +274,126 @@ impl RawFloat for f64 {
     }
 }
 
+impl RawFloat for F16 {
+    type Bits = u16;
+
+    const SIG_BITS: u8 = 11;
+    const EXP_BITS: u8 = 5;
+    const NATIVE_ARITHMETIC: bool = false;
+    const CEIL_LOG5_OF_MAX_SIG: i16 = 5;
+    const MAX_NORMAL_DIGITS: usize = 4;
+    const INF_CUTOFF: i64 = 6;
+    const ZERO_CUTOFF: i64 = -10;
+    const MIN_EXPONENT_ROUND_TO_EVEN: i32 = -22;
+    const MAX_EXPONENT_ROUND_TO_EVEN: i32 = 5;
+    const SMALLEST_POWER_OF_TEN: i32 = -27;
+    const LARGEST_POWER_OF_TEN: i32 = 4;
+    const ZERO: Self = F16::from_bits(0);
+    other_constants!(F16);
+
+    /// Returns the mantissa, exponent and sign as integers.
+    fn integer_decode(self) -> (u64, i16, i8) {
+        let bits = self.to_bits();
+        let sign: i8 = if bits >> 15 == 0 { 1 } else { -1 };
+        let mut exponent: i16 = ((bits >> 10) & 0x1f) as i16;
+        let mantissa = if exponent == 0 { (bits & 0x3ff) << 1 } else { (bits & 0x3ff) | 0x400 };
+        // Exponent bias + mantissa shift
+        exponent -= 15 + 10;
+        (mantissa as u64, exponent, sign)
+    }
+
+    fn unpack(self) -> Unpacked {
+        let (sig, exp, _sig) = self.integer_decode();
+        Unpacked::new(sig, exp)
+    }
+
+    fn from_int(x: u64) -> F16 {
+        // Small enough integers are exact in both `f32` and `F16`.
+        debug_assert!(x <= Self::MAX_SIG);
+        F16::from_f32(x as f32)
+    }
+
+    fn short_fast_pow10(e: usize) -> Self {
+        table::F16_SHORT_POWERS[e]
+    }
+
+    fn classify(self) -> FpCategory {
+        match (self.to_bits() & 0x7c00, self.to_bits() & 0x3ff) {
+            (0, 0) => Zero,
+            (0, _) => Subnormal,
+            (0x7c00, 0) => Infinite,
+            (0x7c00, _) => Nan,
+            _ => Normal,
+        }
+    }
+    fn to_bits(self) -> Self::Bits {
+        self.to_bits()
+    }
+    fn from_bits(v: Self::Bits) -> Self {
+        Self::from_bits(v)
+    }
+}
+
+impl RawFloat for BF16 {
+    type Bits = u16;
+
+    const SIG_BITS: u8 = 8;
+    const EXP_BITS: u8 = 8;
+    const NATIVE_ARITHMETIC: bool = false;
+    const CEIL_LOG5_OF_MAX_SIG: i16 = 4;
+    const MAX_NORMAL_DIGITS: usize = 35;
+    const INF_CUTOFF: i64 = 40;
+    const ZERO_CUTOFF: i64 = -44;
+    const MIN_EXPONENT_ROUND_TO_EVEN: i32 = -24;
+    const MAX_EXPONENT_ROUND_TO_EVEN: i32 = 3;
+    const SMALLEST_POWER_OF_TEN: i32 = -60;
+    const LARGEST_POWER_OF_TEN: i32 = 38;
+    const ZERO: Self = BF16::from_bits(0);
+    other_constants!(BF16);
+
+    /// Returns the mantissa, exponent and sign as integers.
+    fn integer_decode(self) -> (u64, i16, i8) {
+        let bits = self.to_bits();
+        let sign: i8 = if bits >> 15 == 0 { 1 } else { -1 };
+        let mut exponent: i16 = ((bits >> 7) & 0xff) as i16;
+        let mantissa = if exponent == 0 { (bits & 0x7f) << 1 } else { (bits & 0x7f) | 0x80 };
+        // Exponent bias + mantissa shift
+        exponent -= 127 + 7;
+        (mantissa as u64, exponent, sign)
+    }
+
+    fn unpack(self) -> Unpacked {
+        let (sig, exp, _sig) = self.integer_decode();
+        Unpacked::new(sig, exp)
+    }
+
+    fn from_int(x: u64) -> BF16 {
+        // Small enough integers are exact in both `f32` and `BF16`.
+        debug_assert!(x <= Self::MAX_SIG);
+        BF16::from_f32(x as f32)
+    }
+
+    fn short_fast_pow10(e: usize) -> Self {
+        table::BF16_SHORT_POWERS[e]
+    }
+
+    fn classify(self) -> FpCategory {
+        match (self.to_bits() & 0x7f80, self.to_bits() & 0x7f) {
+            (0, 0) => Zero,
+            (0, _) => Subnormal,
+            (0x7f80, 0) => Infinite,
+            (0x7f80, _) => Nan,
+            _ => Normal,
+        }
+    }
+    fn to_bits(self) -> Self::Bits {
+        self.to_bits()
+    }
+    fn from_bits(v: Self::Bits) -> Self {
+        Self::from_bits(v)
+    }
+}
+
 /// Converts an `Fp` to the closest machine float type.
 /// Does not handle subnormal results.
 pub fn fp_to_float<T: RawFloat>(x: Fp) -> T {
@@ -302,6 +453,18 @@ pub fn encode_subnormal<T: RawFloat>(significand: u64) -> T {
     T::from_bits(significand.try_into().unwrap_or_else(|_| unreachable!()))
 }
 
+/// Encode a positive NaN with the given quiet bit and payload. The payload must fit into the
+/// significand bits below the quiet bit, and be non-zero for signalling NaNs (which would be
+/// infinity otherwise).
+pub fn encode_nan<T: RawFloat>(quiet: bool, payload: u64) -> T {
+    let quiet_bit = 1 << (T::EXPLICIT_SIG_BITS - 1);
+    assert!(payload < quiet_bit, "encode_nan: payload too large");
+    assert!(quiet || payload != 0, "encode_nan: signalling NaN without payload");
+    let sig_enc = if quiet { quiet_bit | payload } else { payload };
+    let bits = (T::MAX_ENCODED_EXP as u64) << T::EXPLICIT_SIG_BITS | sig_enc;
+    T::from_bits(bits.try_into().unwrap_or_else(|_| unreachable!()))
+}
+
 /// Approximate a bignum with an Fp. Rounds within 0.5 ULP with half-to-even.
 pub fn big_to_fp(f: &Big) -> Fp {
     let end = f.bit_length();
@@ -324,21 +487,15 @@ pub fn big_to_fp(f: &Big) -> Fp {
 }
 
 /// Finds the largest floating point number strictly smaller than the argument.
-/// Does not handle subnormals, zero, or exponent underflow.
+/// Handles subnormals and infinity with the same trick as `next_float`, but not zero.
 pub fn prev_float<T: RawFloat>(x: T) -> T {
     match x.classify() {
-        Infinite => panic!("prev_float: argument is infinite"),
         Nan => panic!("prev_float: argument is NaN"),
-        Subnormal => panic!("prev_float: argument is subnormal"),
         Zero => panic!("prev_float: argument is zero"),
-        Normal => {
-            let Unpacked { sig, k } = x.unpack();
-            if sig == T::MIN_SIG {
-                encode_normal(Unpacked::new(T::MAX_SIG, k - 1))
-            } else {
-                encode_normal(Unpacked::new(sig - 1, k))
-            }
-        }
+        // Decrementing the encoding of infinity gives the largest finite value, and a borrow
+        // from the exponent gives the largest significand with the next smaller exponent.
+        // This works for the smallest normal number too, the result is the largest subnormal.
+        Infinite | Subnormal | Normal => T::from_bits(x.to_bits() - T::Bits::from(1u8)),
     }
 }
 
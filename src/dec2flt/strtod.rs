@@ -0,0 +1,191 @@
+//! A drop-in for C's `strtod`, reproducing the observable behavior of glibc.
+//!
+//! The conversion itself is the same as everywhere else, the interesting part is deciding when C
+//! would set `errno` to `ERANGE`. glibc does so when the result overflows to infinity, and when
+//! it underflows: the result is *tiny* and *inexact*. Tininess is detected after rounding, as
+//! on x86-64 and AArch64: A value is tiny if rounding it to the full significand width of a
+//! normal number, with an unbounded exponent range, gives something below `MIN_POSITIVE`. So
+//! exact subnormals are fine, and values just below `MIN_POSITIVE` that round up to it with
+//! full precision aren't an error either.
+//!
+//! Both conditions depend on the exact value of the input, so they are checked with exact
+//! comparisons. This only happens for subnormal results and `MIN_POSITIVE`, which are rare
+//! enough that the bignum arithmetic doesn't matter.
+//!
+//! glibc also reads the `(n-char-sequence)` after a `nan` with `strtoull`, which sets `errno` to
+//! `ERANGE` if it starts with an integer that doesn't fit into 64 bits.
+//!
+//! NaN payloads follow glibc as well: An `(n-char-sequence)` that is an integer in C syntax
+//! goes into the significand bits below the quiet bit, with any higher bits cut off. An integer
+//! that overflows saturates, like in `strtoull`.
+//!
+//! The values themselves are correctly rounded. Some glibc versions misround a few subnormal
+//! inputs, such as long decimal expansions or `strtof("0x1.612d29p-129")`, those aren't
+//! reproduced.
+use core::cmp::Ordering;
+use core::num::FpCategory;
+
+use super::algorithm::make_ratio;
+use super::num::{digits_to_big, Big};
+use super::parse::{parse_number_partial_with_zeros, Decimal, ParseResult, Sign};
+use super::rawfp::{self, RawFloat};
+use super::{compact_digits, convert, extract_sign, hex, parse_inf_nan, ParseOptions};
+
+/// The number of significant digits that take part in the exact comparisons. The values we
+/// compare with have at most 769 significant digits, one more than `MAX_SIG_DIGITS`, so we need
+/// a few more digits than the conversion to get a decisive sticky digit.
+const EXACT_DIGITS: usize = 800;
+
+/// The outcome of `dec2flt_strtod`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrtodResult<T> {
+    /// The converted value, or zero if no conversion could be performed.
+    pub value: T,
+    /// The number of bytes consumed, that is, `endptr - nptr`. Zero if no conversion could be
+    /// performed, even if there was leading whitespace.
+    pub len: usize,
+    /// Whether `strtod` would set `errno` to `ERANGE`.
+    pub range_error: bool,
+}
+
+/// Converts the longest prefix of a byte string that is a floating point number, with the
+/// semantics of C's `strtod` (or `strtof`, for `f32`) as implemented by glibc in the C locale.
+///
+/// Leading whitespace is skipped. Then an optional sign is followed by a decimal float, a
+/// hexadecimal float with a `0x` prefix, `inf`, `infinity` or `nan`, optionally followed by a
+/// parenthesized sequence of letters, digits and underscores, all in any case. Parsing stops at
+/// the first character that can't continue the number.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::dec2flt_strtod;
+///
+/// let r = dec2flt_strtod::<f64>(b"  0x1.8p1,");
+/// assert_eq!((r.value, r.len, r.range_error), (3.0, 9, false));
+/// let r = dec2flt_strtod::<f64>(b"-1e-400");
+/// assert_eq!((r.value, r.len, r.range_error), (-0.0, 7, true));
+/// ```
+pub fn dec2flt_strtod<T: RawFloat>(s: &[u8]) -> StrtodResult<T> {
+    const OPTIONS: ParseOptions = ParseOptions::new().hex(true);
+    let no_conversion = StrtodResult { value: T::ZERO, len: 0, range_error: false };
+    let start = s.iter().position(|&c| !is_space(c)).unwrap_or(s.len());
+    if start == s.len() {
+        return no_conversion;
+    }
+    let (sign, rest) = extract_sign(&s[start..]);
+    let (result, len, zeros) = parse_number_partial_with_zeros(rest, &OPTIONS);
+    let (flt, len, range_error) = match result {
+        ParseResult::Valid(decimal) => {
+            let flt = convert(decimal.clone(), zeros);
+            (flt, len, is_range_error(flt, |m, k| compare_decimal(&decimal, m, k)))
+        }
+        ParseResult::Hexadecimal(h) => {
+            let flt = hex::convert(h.clone());
+            (flt, len, is_range_error(flt, |m, k| hex::compare(&h, m, k)))
+        }
+        ParseResult::ShortcutToInf => (T::INFINITY, len, true),
+        ParseResult::ShortcutToZero => (T::ZERO, len, true),
+        ParseResult::Invalid(..) => match parse_inf_nan::<T>(rest) {
+            Some((flt, len)) if flt.classify() == FpCategory::Nan => {
+                let (payload, n, overflow) = n_char_sequence(&rest[len..]);
+                let payload = payload & ((1 << (T::EXPLICIT_SIG_BITS - 1)) - 1);
+                (rawfp::encode_nan(true, payload), len + n, overflow)
+            }
+            Some((flt, len)) => (flt, len, false),
+            None => return no_conversion,
+        },
+    };
+
+    let len = s.len() - rest.len() + len;
+    match sign {
+        Sign::Positive => StrtodResult { value: flt, len, range_error },
+        Sign::Negative => StrtodResult { value: -flt, len, range_error },
+    }
+}
+
+/// The characters for which C's `isspace` is true in the C locale.
+fn is_space(c: u8) -> bool {
+    matches!(c, b' ' | b'\t' | b'\n' | b'\x0b' | b'\x0c' | b'\r')
+}
+
+/// Returns the payload encoded by the `(n-char-sequence)` that may follow a `nan`, along with
+/// its length, which is zero if there is none (or it isn't closed), and whether it overflowed.
+/// Like glibc, we only take the payload from sequences that are entirely an integer as
+/// `strtoull` reads it: decimal, octal with a leading `0`, or hexadecimal with `0x`, saturating
+/// on overflow. `strtoull` sets `errno` to `ERANGE` when the integer it reads overflows, even
+/// if characters follow that make glibc ignore the payload, so that is reported either way.
+fn n_char_sequence(s: &[u8]) -> (u64, usize, bool) {
+    if s.first() != Some(&b'(') {
+        return (0, 0, false);
+    }
+    let n = s[1..].iter().take_while(|c| c.is_ascii_alphanumeric() || **c == b'_').count();
+    if s.get(n + 1) != Some(&b')') {
+        return (0, 0, false);
+    }
+    let (radix, digits) = match &s[1..n + 1] {
+        [b'0', b'x' | b'X', digits @ ..] if !digits.is_empty() => (16, digits),
+        [b'0', digits @ ..] => (8, digits),
+        digits => (10, digits),
+    };
+    let (mut payload, mut overflow) = (0u64, false);
+    for &c in digits {
+        let digit = match (c as char).to_digit(radix) {
+            Some(digit) => digit as u64,
+            None => return (0, n + 2, overflow),
+        };
+        match payload.checked_mul(radix as u64).and_then(|p| p.checked_add(digit)) {
+            Some(p) => payload = p,
+            None => {
+                payload = !0;
+                overflow = true;
+            }
+        }
+    }
+    (payload, n + 2, overflow)
+}
+
+/// Decides whether glibc would report `ERANGE` for a finite input that was rounded to `flt`.
+/// `compare(m, k)` compares the exact value of the input with `m * 2^k`.
+fn is_range_error<T, F>(flt: T, compare: F) -> bool
+where
+    T: RawFloat,
+    F: Fn(u64, i16) -> Ordering,
+{
+    match flt.classify() {
+        FpCategory::Infinite => true,
+        FpCategory::Zero => compare(0, 0) != Ordering::Equal,
+        // Subnormal results are always tiny, so it all comes down to exactness.
+        FpCategory::Subnormal => {
+            let u = flt.unpack();
+            compare(u.sig, u.k) != Ordering::Equal
+        }
+        // `MIN_POSITIVE` is the result for everything that rounds to it with full precision,
+        // and for values down to a quarter of the subnormal spacing lower still, which round to
+        // `MIN_POSITIVE` only because of the limited exponent range. Those are tiny (and
+        // certainly inexact).
+        FpCategory::Normal => {
+            let u = flt.unpack();
+            u.sig == T::MIN_SIG
+                && u.k == T::MIN_EXP_INT
+                && compare(4 * T::MIN_SIG - 1, T::MIN_EXP_INT - 2) == Ordering::Less
+        }
+        FpCategory::Nan => false,
+    }
+}
+
+/// Compares the exact value of `decimal` with `m * 2^k`, which must be tiny unless `m` is zero.
+fn compare_decimal(decimal: &Decimal<'_>, m: u64, k: i16) -> Ordering {
+    let mut buffer = [0; EXACT_DIGITS + 1];
+    let decimal = compact_digits(decimal, &mut buffer);
+    if m == 0 {
+        let is_zero = decimal.integral.is_empty() && decimal.fractional.is_empty();
+        return if is_zero { Ordering::Equal } else { Ordering::Greater };
+    }
+    // Both values are close to `MIN_POSITIVE`, so this exponent is rather small.
+    let e = decimal.exp - decimal.fractional.len() as i64;
+    let mut x = digits_to_big(decimal.integral, decimal.fractional);
+    let mut y = Big::from_u64(m);
+    make_ratio(&mut x, &mut y, e as i16, k);
+    x.cmp(&y)
+}
@@ -0,0 +1,371 @@
+//! Converting decimal strings into the binary formats with 15 exponent bits: IEEE 754 binary128
+//! ("quad precision") and the 80-bit extended precision format of the x87 FPU.
+//!
+//! The `RawFloat` trait and the algorithms built on it assume that a significand fits into a
+//! `u64` with room to spare, and that exponents fit into an `i16`, which is what keeps them fast
+//! for `f32` and `f64`. Rather than widening all of that, these formats take their own, exact
+//! route: it scales the decimal digits and the power of ten such that their integer quotient has
+//! two more bits than the significand, divides, and rounds with the help of those two bits and
+//! the remainder. This is a lot slower than the main algorithms, but each conversion is still a
+//! single bignum division.
+
+use core::fmt;
+
+use super::num::{self, Digit, DECIMAL_DIGITS_PER_DIGIT};
+use super::parse::{parse_decimal_with_zeros, Decimal, ParseResult, Sign, ZeroCounts};
+use super::{compact_digits, extract_sign, parse_inf_nan, pfe_empty, pfe_invalid, simplify};
+use super::{FloatErrorKind, ParseFloatError};
+
+// These hold the operands of the division in `scale`, see there for their bounds.
+#[cfg(not(target_pointer_width = "64"))]
+use crate::bignum::Big32x1220 as Big;
+#[cfg(target_pointer_width = "64")]
+use crate::bignum::Big64x610 as Big;
+
+/// An IEEE 754 binary128 number, stored as its bit pattern.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct F128(u128);
+
+impl F128 {
+    /// Positive infinity.
+    pub const INFINITY: F128 = F128(0x7fff << 112);
+    /// Not a number, specifically the positive quiet NaN without payload.
+    pub const NAN: F128 = F128(0xffff << 111);
+    /// The largest finite value, about 1.19 * 10<sup>4932</sup>.
+    pub const MAX: F128 = F128((0x7fff << 112) - 1);
+    /// The smallest positive normal value, 2<sup>-16382</sup>.
+    pub const MIN_POSITIVE: F128 = F128(1 << 112);
+
+    /// Performs a raw transmutation from an integer.
+    pub const fn from_bits(v: u128) -> F128 {
+        F128(v)
+    }
+
+    /// Performs a raw transmutation to an integer.
+    pub const fn to_bits(self) -> u128 {
+        self.0
+    }
+}
+
+impl fmt::Debug for F128 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "F128({:#034x})", self.0)
+    }
+}
+
+/// A binary format with 15 exponent bits and a bias of 16383.
+struct Format {
+    /// The number of bits in the significand, *including* the integer bit.
+    sig_bits: u32,
+    /// Whether the integer bit is stored, rather than implied by a non-zero exponent.
+    explicit_int_bit: bool,
+}
+
+impl Format {
+    /// The number of bits the significand takes in the encoding.
+    const fn stored_sig_bits(&self) -> u32 {
+        self.sig_bits - !self.explicit_int_bit as u32
+    }
+
+    /// The place value of the least significant bit of subnormals, which is also the exponent
+    /// of the smallest subnormal.
+    const fn min_exp_int(&self) -> i64 {
+        MIN_EXP - (self.sig_bits as i64 - 1)
+    }
+
+    /// Positive infinity. With an explicit integer bit, only the encoding that has it set is
+    /// a proper infinity.
+    const fn infinity(&self) -> u128 {
+        (0x7fff << self.stored_sig_bits()) | (self.explicit_int_bit as u128) << (self.sig_bits - 1)
+    }
+
+    /// The positive quiet NaN without payload.
+    const fn nan(&self) -> u128 {
+        self.infinity() | 1 << (self.sig_bits - 2)
+    }
+
+    const fn sign_bit(&self) -> u128 {
+        1 << (self.stored_sig_bits() + 15)
+    }
+}
+
+const BINARY128: Format = Format { sig_bits: 113, explicit_int_bit: false };
+
+const X87_EXTENDED: Format = Format { sig_bits: 64, explicit_int_bit: true };
+
+/// The exponent of the smallest normal number.
+const MIN_EXP: i64 = -16382;
+
+/// Like `MAX_SIG_DIGITS` for `f64`: A decimal value exactly halfway between two adjacent
+/// binary128 values has at most 11564 significant digits, which covers the 11515 of x87, too.
+const MAX_SIG_DIGITS: usize = 11564;
+
+/// When the most significant decimal digit has a place value greater than this, the number
+/// is certainly rounded to infinity, in both formats.
+const INF_CUTOFF: i64 = 4934;
+
+/// When the most significant decimal digit has a place value less than this, the number
+/// is certainly rounded to zero, in both formats.
+const ZERO_CUTOFF: i64 = -4968;
+
+/// Converts a decimal string into the nearest binary128 number.
+///
+/// This accepts the same syntax as `dec2flt`. Since there is no `f128` type, the result comes
+/// as its bit pattern.
+///
+/// Integers of up to 38 significant digits and short decimals such as `0.125` are converted with
+/// `u128` arithmetic. All other numbers take a bignum division, which needs about 42 KB of stack:
+/// up to 11,565 bytes for the digits, and six bignums of just under 5 KB each for the operands,
+/// the results and the working copies of the division.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::dec2flt_f128;
+///
+/// assert_eq!(dec2flt_f128("1").unwrap().to_bits(), 0x3fff << 112);
+/// assert_eq!(dec2flt_f128("0.1").unwrap().to_bits(), 0x3ffb_9999_9999_9999_9999_9999_9999_999a);
+/// ```
+pub fn dec2flt_f128(s: &str) -> Result<F128, ParseFloatError> {
+    dec2flt_wide(s.as_bytes(), &BINARY128).map(F128)
+}
+
+/// Converts a decimal string into the nearest number in the 80-bit extended precision format
+/// of the x87 FPU, as the 10 bytes that `fstp tbyte` would store.
+///
+/// These are the 64-bit significand, which includes the integer bit, followed by the sign and
+/// the 15-bit exponent, all little endian. The integer bit is set for all normal numbers,
+/// infinities and NaNs, and clear for subnormals and zero, so the result is never one of the
+/// unnormals or pseudo-denormals that the FPU accepts, but doesn't produce itself. In
+/// particular, a subnormal that rounds up to the smallest normal number gets the exponent 1.
+///
+/// Like `dec2flt_f128`, this takes about 42 KB of stack, except for integers and short decimals.
+///
+/// # Example
+///
+/// ```
+/// use dec2flt::dec2flt::dec2flt_x87_extended;
+///
+/// let one = dec2flt_x87_extended("1").unwrap();
+/// assert_eq!(one, [0, 0, 0, 0, 0, 0, 0, 0x80, 0xff, 0x3f]);
+/// ```
+pub fn dec2flt_x87_extended(s: &str) -> Result<[u8; 10], ParseFloatError> {
+    let bits = dec2flt_wide(s.as_bytes(), &X87_EXTENDED)?;
+    let mut bytes = [0; 10];
+    bytes.copy_from_slice(&bits.to_le_bytes()[..10]);
+    Ok(bytes)
+}
+
+/// Like `dec2flt_with`, but for the formats in this module, whose bits are returned.
+fn dec2flt_wide(s: &[u8], format: &Format) -> Result<u128, ParseFloatError> {
+    let (negative, number) = parse_number(s)?;
+    let abs = match number {
+        Number::Decimal(decimal, zeros) => convert(decimal, zeros, format),
+        Number::Huge | Number::Infinity => format.infinity(),
+        Number::Tiny => 0,
+        Number::Nan => format.nan(),
+    };
+    if negative { Ok(abs | format.sign_bit()) } else { Ok(abs) }
+}
+
+/// An unsigned number as written, before it is converted into any particular format.
+pub(super) enum Number<'a> {
+    /// A decimal, with the zero runs at the ends of its digit runs.
+    Decimal(Decimal<'a>, ZeroCounts),
+    /// A decimal whose exponent is so large that it exceeds every finite number.
+    Huge,
+    /// A non-zero decimal whose exponent is so small that it rounds to zero, unless the format
+    /// has no zero.
+    Tiny,
+    Infinity,
+    Nan,
+}
+
+/// The parsing half of `dec2flt_wide`, shared with the formats of `fp8`, which leaves the
+/// conversion to the caller. Returns whether the number is negative, and its absolute value.
+pub(super) fn parse_number(s: &[u8]) -> Result<(bool, Number<'_>), ParseFloatError> {
+    if s.is_empty() {
+        return Err(pfe_empty());
+    }
+    let (sign, rest) = extract_sign(s);
+    let sign_len = s.len() - rest.len();
+    let (result, zeros) = parse_decimal_with_zeros(rest);
+    let number = match result {
+        ParseResult::Valid(decimal) => Number::Decimal(decimal, zeros),
+        ParseResult::ShortcutToInf => Number::Huge,
+        ParseResult::ShortcutToZero => Number::Tiny,
+        // The names of the special values are the same for all types.
+        ParseResult::Invalid(kind, index) => match parse_inf_nan::<f64>(rest) {
+            Some((x, len)) if len == rest.len() => {
+                if x.is_nan() { Number::Nan } else { Number::Infinity }
+            }
+            Some((_, len)) => {
+                return Err(pfe_invalid(FloatErrorKind::TrailingCharacters, sign_len + len));
+            }
+            None => return Err(pfe_invalid(kind, sign_len + index)),
+        },
+        ParseResult::Hexadecimal(_) => unreachable!("hexadecimal floats weren't asked for"),
+    };
+    Ok((matches!(sign, Sign::Negative), number))
+}
+
+/// Converts the unsigned decimal to the bits of the nearest number in `format`.
+fn convert(mut decimal: Decimal<'_>, zeros: ZeroCounts, format: &Format) -> u128 {
+    simplify(&mut decimal, zeros);
+    let scaled = match scale_small(&decimal, format.sig_bits) {
+        Some(scaled) => scaled,
+        None => scale(decimal, format.sig_bits),
+    };
+    let (q, e, inexact) = match scaled {
+        Scaled::Zero | Scaled::Tiny => return 0,
+        Scaled::Huge => return format.infinity(),
+        Scaled::Bits { q, e, inexact } => (q, e, inexact),
+    };
+    let min_exp_int = format.min_exp_int();
+    let (sig, unit) = round(q, e, inexact, format.sig_bits, min_exp_int);
+    let bits = if !format.explicit_int_bit {
+        // The hidden bit of normal numbers adds one to the biased exponent, which is just right,
+        // since it is one more than that of subnormals. Likewise, if rounding up overflows the
+        // significand, the carry increments the exponent.
+        (((unit - min_exp_int) as u128) << (format.sig_bits - 1)) + sig
+    } else {
+        // The same, except that a carry has to be moved into the exponent by hand, and the
+        // integer bit stays in the significand. A subnormal that rounds up to the smallest
+        // normal number thus gets the exponent 1, and doesn't become a pseudo-denormal.
+        let (unit, sig) =
+            if sig >> format.sig_bits != 0 { (unit + 1, sig >> 1) } else { (unit, sig) };
+        let biased = (unit - min_exp_int) as u128 + (sig >> (format.sig_bits - 1));
+        biased << format.sig_bits | sig
+    };
+    // Everything from infinity up is what the numbers past the largest finite one encode as.
+    bits.min(format.infinity())
+}
+
+/// A decimal as a binary number with a few bits more than a significand.
+pub(super) enum Scaled {
+    /// The decimal is zero.
+    Zero,
+    /// The decimal is positive, but less than half the smallest binary128 subnormal.
+    Tiny,
+    /// The decimal is larger than the largest finite binary128 number.
+    Huge,
+    /// The decimal is `q * 2^e`, plus a little more if `inexact` is set. `q` has at least two
+    /// bits more than asked for.
+    Bits { q: u128, e: i64, inexact: bool },
+}
+
+/// Scales the unsigned decimal exactly, for rounding to a significand of `sig_bits` bits.
+pub(super) fn scale(decimal: Decimal<'_>, sig_bits: u32) -> Scaled {
+    let mut buffer = [0; MAX_SIG_DIGITS + 1];
+    let decimal = compact_digits(&decimal, &mut buffer);
+    if decimal.integral.is_empty() && decimal.fractional.is_empty() {
+        return Scaled::Zero;
+    }
+    // See `trivial_cases`, these also keep all the numbers below within the bounds of `Big`.
+    let max_place = decimal.exp + decimal.integral.len() as i64;
+    if max_place > INF_CUTOFF {
+        return Scaled::Huge;
+    } else if max_place < ZERO_CUTOFF {
+        return Scaled::Tiny;
+    }
+
+    // The value is `num / den * 2^e`, where `num / den` lies in `[2^(k - 1), 2^(k + 1))`. The
+    // powers of two stay out of the bignums: With at most `MAX_SIG_DIGITS + 1` digits and the
+    // cutoffs above, `num` starts out with at most 38,419 bits and `den` with at most 38,389,
+    // so neither exceeds 38,505 bits after the scaling below, which fits into `Big`.
+    let e = decimal.exp - decimal.fractional.len() as i64;
+    let mut num = digits_to_big(decimal.integral, decimal.fractional);
+    let mut den = Big::from_small(1);
+    if e >= 0 {
+        num.mul_pow5(e as usize);
+    } else {
+        den.mul_pow5(-e as usize);
+    }
+    let k = num.bit_length() as i64 - den.bit_length() as i64;
+    // Scale by `2^t`, such that the quotient has at least two bits more than the significand.
+    let t = sig_bits as i64 + 2 - k;
+    if t >= 0 {
+        num.mul_pow2(t as usize);
+    } else {
+        den.mul_pow2(-t as usize);
+    }
+    let (mut q, mut r) = (Big::from_small(0), Big::from_small(0));
+    num.div_rem(&den, &mut q, &mut r);
+    // The quotient is less than `2^(sig_bits + 3)`, so this doesn't lose any bits.
+    let q = q.digits().iter().rev().fold(0, |acc, &d| acc << Digit::BITS | d as u128);
+    Scaled::Bits { q, e: e - t, inexact: !r.is_zero() }
+}
+
+/// Like `scale`, but only for decimals whose digits fit into a `u128`, and which are integers or
+/// have so few fractional digits that `u128` arithmetic suffices. Returns `None` for all others.
+/// The decimal must have been simplified.
+fn scale_small(decimal: &Decimal<'_>, sig_bits: u32) -> Option<Scaled> {
+    // `u128::MAX` has 39 digits, so any 38 of them fit.
+    if decimal.integral.len() + decimal.fractional.len() > 38 {
+        return None;
+    }
+    let digits = decimal.integral.iter().chain(decimal.fractional);
+    let f = digits.fold(0, |f, &d| f * 10 + (d - b'0') as u128);
+    if f == 0 {
+        return Some(Scaled::Zero);
+    }
+    let e = decimal.exp - decimal.fractional.len() as i64;
+    // Larger powers of ten don't fit.
+    if e.abs() > 38 {
+        return None;
+    }
+    let bits = |x: u128| 128 - x.leading_zeros();
+    if e >= 0 {
+        // An integer, which only needs to be padded to enough bits.
+        let q = f.checked_mul(10u128.pow(e as u32))?;
+        let t = (sig_bits + 2).saturating_sub(bits(q));
+        return Some(Scaled::Bits { q: q << t, e: -(t as i64), inexact: false });
+    }
+    // As in `scale`, but `num` and `den` must fit into a `u128`.
+    let den = 10u128.pow(-e as u32);
+    let t = (sig_bits + 2 + bits(den)).saturating_sub(bits(f));
+    if t > f.leading_zeros() {
+        return None;
+    }
+    let num = f << t;
+    Some(Scaled::Bits { q: num / den, e: -(t as i64), inexact: num % den != 0 })
+}
+
+/// Rounds `q * 2^e`, plus a little more if `inexact`, to `sig_bits` significant bits, with ties
+/// to even, but to no place value less than `min_exp_int`. Returns the significand and the
+/// place value of its last bit. The significand has one bit more if rounding up carried.
+pub(super) fn round(
+    q: u128,
+    e: i64,
+    inexact: bool,
+    sig_bits: u32,
+    min_exp_int: i64,
+) -> (u128, i64) {
+    let top = e + 127 - q.leading_zeros() as i64;
+    // The place value of the last significand bit, which is fixed for subnormals.
+    let unit = (top - (sig_bits as i64 - 1)).max(min_exp_int);
+    let excess = (unit - e) as u32;
+    if excess >= 128 {
+        // Less than a quarter of the smallest subnormal.
+        return (0, unit);
+    }
+    let half = 1 << (excess - 1);
+    let rest = q & ((1 << excess) - 1);
+    let mut sig = q >> excess;
+    if rest > half || (rest == half && (inexact || sig % 2 == 1)) {
+        sig += 1;
+    }
+    (sig, unit)
+}
+
+/// Converts a string of ASCII digits into a bignum, like `num::digits_to_big`.
+fn digits_to_big(integral: &[u8], fractional: &[u8]) -> Big {
+    let mut f = Big::from_small(0);
+    for &digits in [integral, fractional].iter() {
+        for run in digits.chunks(DECIMAL_DIGITS_PER_DIGIT) {
+            let scale = (10 as Digit).pow(run.len() as u32);
+            f.mul_small_add(scale, num::from_str_unchecked(run) as Digit);
+        }
+    }
+    f
+}
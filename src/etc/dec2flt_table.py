@@ -0,0 +1,165 @@
+#!/usr/bin/env python3
+
+"""
+Generate powers of ten using William Clinger's ``AlgorithmM`` for use in
+decimal to floating point conversions.
+
+Specifically, computes and outputs (as Rust code) a table of 10^e for some
+range of exponents e. The output is one array of 64 bit significands and
+another array of corresponding base two exponents. The approximations are
+normalized and rounded perfectly, i.e., within 0.5 ULP of the true value.
+
+The output also includes tables of the exact small powers of ten for the fast
+path of each type, and the powers of five normalized to 128 bits for the
+Eisel-Lemire algorithm.
+
+Run this script from the repository root and redirect the output into
+src/dec2flt/table.rs.
+"""
+from fractions import Fraction
+
+HEADER = """
+//! Tables of approximations of powers of ten, and of powers of five in 128 bits.
+//! DO NOT MODIFY: Generated by `src/etc/dec2flt_table.py`
+
+use crate::dec2flt::half::{BF16, F16};
+"""
+
+MIN_E = -305
+MAX_E = 305
+
+SMALLEST_POWER_OF_FIVE = -342
+LARGEST_POWER_OF_FIVE = 308
+
+
+def algorithm_m(f, e):
+    """The 64 bit significand and base two exponent of ``f * 10^e``, rounded to nearest, ties
+    to even."""
+    assert f > 0
+    x = Fraction(f) * Fraction(10) ** e
+    k = x.numerator.bit_length() - x.denominator.bit_length() - 64
+    # Now `x / 2^k` is within a factor of two of `[2^63, 2^64)`.
+    while x / Fraction(2) ** k >= 2 ** 64:
+        k += 1
+    while x / Fraction(2) ** k < 2 ** 63:
+        k -= 1
+    z = round(x / Fraction(2) ** k)
+    if z == 2 ** 64:
+        z, k = 2 ** 63, k + 1
+    return z, k
+
+
+def print_proper_powers():
+    sigs, exps = [], []
+    for e in range(MIN_E, MAX_E + 1):
+        z, k = algorithm_m(1, e)
+        sigs.append(z)
+        exps.append(k)
+    print("pub const MIN_E: i16 = {};".format(MIN_E))
+    print("pub const MAX_E: i16 = {};".format(MAX_E))
+    print()
+    print("#[rustfmt::skip]")
+    typ = "([u64; {0}], [i16; {0}])".format(len(sigs))
+    print("pub static POWERS: {} = (".format(typ))
+    print("    [")
+    for z in sigs:
+        print("        0x{:x},".format(z))
+    print("    ],")
+    print("    [")
+    for k in exps:
+        print("        {},".format(k))
+    print("    ],")
+    print(");")
+
+
+def short_powers(significand_bits):
+    """The exponents of the powers of ten that are exact with this many significand bits, which
+    are those whose power of five fits."""
+    e = 0
+    while 5 ** (e + 1) < 2 ** significand_bits:
+        e += 1
+    return range(e + 1)
+
+
+def print_short_powers(num_bits, significand_bits):
+    print("#[rustfmt::skip]")
+    exps = short_powers(significand_bits)
+    print("pub const F{}_SHORT_POWERS: [f{}; {}] = [".format(num_bits, num_bits, len(exps)))
+    for e in exps:
+        print("    1e{},".format(e))
+    print("];")
+
+
+def encode(x, significand_bits, exponent_bits):
+    """The bits of the positive integer `x`, which must be exact in the format."""
+    k = x.bit_length() - 1
+    bias = 2 ** (exponent_bits - 1) - 1
+    assert x % 2 ** max(k - (significand_bits - 1), 0) == 0
+    explicit = (x << (significand_bits - 1) >> k) - 2 ** (significand_bits - 1)
+    return (k + bias) << (significand_bits - 1) | explicit
+
+
+def print_short_powers_by_bits(name, significand_bits, exponent_bits):
+    print("#[rustfmt::skip]")
+    exps = short_powers(significand_bits)
+    print("pub const {}_SHORT_POWERS: [{}; {}] = [".format(name, name, len(exps)))
+    for e in exps:
+        bits = encode(10 ** e, significand_bits, exponent_bits)
+        print("    {}::from_bits(0x{:04x}), // 1e{}".format(name, bits, e))
+    print("];")
+
+
+def power_of_five_128(q):
+    """The power of five normalized to 128 bits. Positive powers are truncated, negative powers
+    are `2^b / 5^-q` for a large enough `b`, rounded up. For the smaller ones, `b` is larger
+    still, and the quotient is truncated to 128 bits after rounding up, like in fast_float."""
+    if q >= 0:
+        z = 5 ** q
+        shift = z.bit_length() - 128
+        z = z >> shift if shift > 0 else z << -shift
+    else:
+        d = 5 ** -q
+        n = (d - 1).bit_length()
+        b = n + 127 if q >= -27 else 2 * n + 128
+        z = 2 ** b // d + 1
+        z >>= max(z.bit_length() - 128, 0)
+        z <<= 128 - z.bit_length()
+    return z >> 64, z & (2 ** 64 - 1)
+
+
+def print_powers_of_five():
+    print("pub const SMALLEST_POWER_OF_FIVE: i32 = {};".format(SMALLEST_POWER_OF_FIVE))
+    print("pub const LARGEST_POWER_OF_FIVE: i32 = {};".format(LARGEST_POWER_OF_FIVE))
+    print("pub const N_POWERS_OF_FIVE: usize = "
+          "(LARGEST_POWER_OF_FIVE - SMALLEST_POWER_OF_FIVE + 1) as usize;")
+    print()
+    print("/// The powers of five normalized to 128 bits, for the Eisel-Lemire algorithm. "
+          "Positive powers")
+    print("/// are truncated, negative powers are `2^b / 5^-q` for a large enough `b`, "
+          "rounded up.")
+    print("#[rustfmt::skip]")
+    print("pub static POWER_OF_FIVE_128: [(u64, u64); N_POWERS_OF_FIVE] = [")
+    for q in range(SMALLEST_POWER_OF_FIVE, LARGEST_POWER_OF_FIVE + 1):
+        hi, lo = power_of_five_128(q)
+        print("    (0x{:016x}, 0x{:016x}), // 5^{}".format(hi, lo, q))
+    print("];")
+
+
+def main():
+    print(HEADER.strip())
+    print()
+    print_proper_powers()
+    print()
+    print_short_powers(32, 24)
+    print()
+    print_short_powers(64, 53)
+    print()
+    print_short_powers_by_bits("F16", 11, 5)
+    print()
+    print_short_powers_by_bits("BF16", 8, 8)
+    print()
+    print_powers_of_five()
+
+
+if __name__ == '__main__':
+    main()
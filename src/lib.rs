@@ -12,6 +12,23 @@ pub mod dec2flt;
 pub mod diy_float;
 
 pub use self::dec2flt::dec2flt as parse;
+pub use self::dec2flt::dec2flt_bytes as parse_bytes;
+pub use self::dec2flt::dec2flt_e4m3fn as parse_e4m3fn;
+pub use self::dec2flt::dec2flt_e5m2 as parse_e5m2;
+pub use self::dec2flt::dec2flt_e8m0 as parse_e8m0;
+pub use self::dec2flt::dec2flt_f128 as parse_f128;
+pub use self::dec2flt::dec2flt_hex as parse_hex;
+pub use self::dec2flt::dec2flt_json as parse_json;
+pub use self::dec2flt::dec2flt_partial as parse_partial;
+pub use self::dec2flt::dec2flt_partial_with_options as parse_partial_with_options;
+pub use self::dec2flt::dec2flt_rust_literal as parse_rust_literal;
+pub use self::dec2flt::dec2flt_rust_literal_as as parse_rust_literal_as;
+pub use self::dec2flt::dec2flt_with_options as parse_with_options;
+pub use self::dec2flt::dec2flt_x87_extended as parse_x87_extended;
+pub use self::dec2flt::dec2flt_strtod as strtod;
+pub use self::dec2flt::{FloatErrorKind, ParseFloatError, ParseOptions, SeparatorPlacement};
+pub use self::dec2flt::{OverflowPolicy, E4M3FN, E5M2, E8M0};
+pub use self::dec2flt::{FloatSuffix, RustFloat, StrtodResult, BF16, F128, F16};
 
 /// Sample function to prevent optimization for binaries.
 #[inline]
@@ -0,0 +1,55 @@
+mod common;
+
+use common::{check_exhaustive, Format};
+use dec2flt::{parse, BF16};
+
+fn parse_bits(s: &str) -> u16 {
+    parse::<BF16>(s).unwrap().to_bits()
+}
+
+#[test]
+pub fn test_parse_bf16() {
+    assert_eq!(parse_bits("1"), 0x3f80);
+    assert_eq!(parse_bits("-2.5"), 0xc020);
+    assert_eq!(parse_bits("3.14159"), 0x4049);
+    assert_eq!(parse_bits("0.1"), 0x3dcd);
+    assert_eq!(parse_bits("1e38"), 0x7e96);
+    assert_eq!(parse_bits("3.39e38"), BF16::MAX.to_bits());
+    assert_eq!(parse_bits("3.4e38"), BF16::INFINITY.to_bits());
+    assert_eq!(parse_bits("1.1754943508222875e-38"), BF16::MIN_POSITIVE.to_bits());
+    assert_eq!(parse_bits("9.18e-41"), 0x0001);
+    assert_eq!(parse_bits("4.59e-41"), 0x0000);
+    assert_eq!(parse_bits("4.6e-41"), 0x0001);
+    assert_eq!(parse_bits("-0"), 0x8000);
+    assert_eq!(parse_bits("-inf"), 0xff80);
+    assert_eq!(parse_bits("NaN"), BF16::NAN.to_bits());
+}
+
+#[test]
+pub fn test_parse_bf16_exhaustive() {
+    let parse = |s: &str| parse::<BF16>(s).map(|x| x.to_bits()).map_err(|e| *e.kind());
+    let to_f64 = |bits| BF16::from_bits(bits).to_f32() as f64;
+    let format = Format {
+        max: BF16::MAX.to_bits(),
+        next: 2f64.powi(128),
+        overflow: Ok(BF16::INFINITY.to_bits()),
+        sign_bit: 0x8000,
+    };
+    check_exhaustive(parse, to_f64, format);
+}
+
+#[test]
+pub fn test_bf16_from_f32() {
+    for bits in 0..=u16::MAX {
+        let x = BF16::from_bits(bits);
+        if !x.to_f32().is_nan() {
+            assert_eq!(BF16::from_f32(x.to_f32()).to_bits(), bits);
+        }
+    }
+    assert_eq!(BF16::from_f32(1.0 + 1.0 / 256.0).to_bits(), 0x3f80);
+    assert_eq!(BF16::from_f32(1.0 + 3.0 / 256.0).to_bits(), 0x3f82);
+    assert_eq!(BF16::from_f32(f32::MAX).to_bits(), 0x7f80);
+    assert_eq!(BF16::from_f32(f32::from_bits(1)).to_bits(), 0x0000);
+    assert_eq!(BF16::from_f32(-f32::INFINITY).to_bits(), 0xff80);
+    assert!(BF16::from_f32(f32::from_bits(0x7f80_0001)).to_f32().is_nan());
+}
@@ -0,0 +1,136 @@
+use dec2flt::bignum::tests::Big8x3 as Big;
+
+/// Checks `div_rem` against the bitwise long division, and against native integers.
+fn check_div_rem(a: &Big, d: &Big) {
+    let (mut q, mut r) = (Big::from_small(0), Big::from_small(0));
+    let (mut q_ref, mut r_ref) = (Big::from_small(0), Big::from_small(0));
+    a.div_rem(d, &mut q, &mut r);
+    a.div_rem_bitwise(d, &mut q_ref, &mut r_ref);
+    assert_eq!(q, q_ref, "{:?} / {:?}", a, d);
+    assert_eq!(r, r_ref, "{:?} % {:?}", a, d);
+
+    let value = |x: &Big| x.digits().iter().rev().fold(0u32, |v, &d| v << 8 | d as u32);
+    assert_eq!(value(&q), value(a) / value(d), "{:?} / {:?}", a, d);
+    assert_eq!(value(&r), value(a) % value(d), "{:?} % {:?}", a, d);
+}
+
+#[test]
+pub fn test_div_rem() {
+    let values = [
+        1, 2, 3, 7, 0x7f, 0x80, 0xff, 0x100, 0x101, 0x17f, 0x1ff, 0x7fff, 0x8000, 0x8001, 0xfe01,
+        0xffff, 0x1_0000, 0x1_00ff, 0x7f_ffff, 0x80_0000, 0x80_00ff, 0xff_fe01, 0xff_ffff,
+    ];
+    for &a in &values {
+        for &d in &values {
+            check_div_rem(&Big::from_u64(a), &Big::from_u64(d));
+        }
+    }
+    check_div_rem(&Big::from_small(0), &Big::from_small(3));
+}
+
+#[test]
+pub fn test_div_rem_random() {
+    // A simple linear congruential generator, mixing up the number of digits.
+    let mut state = 0x2545_f491_4f6c_dd1du64;
+    let mut next = || {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        let bits = (state >> 59) as u32 % 24 + 1;
+        (state >> 32) & ((1 << bits) - 1)
+    };
+    for _ in 0..100_000 {
+        let (a, d) = (next(), next());
+        if d != 0 {
+            check_div_rem(&Big::from_u64(a), &Big::from_u64(d));
+        }
+    }
+}
+
+#[test]
+pub fn test_div_rem_leading_zero_digits() {
+    // The size of a bignum doesn't shrink, so the divisor may have leading zero digits.
+    let mut d = Big::from_u64(0x1_0000);
+    d.sub(&Big::from_u64(0xff00));
+    check_div_rem(&Big::from_u64(0xab_cdef), &d);
+    check_div_rem(&d, &Big::from_u64(0x1234));
+    check_div_rem(&d, &d.clone());
+}
+
+/// Returns the little-endian bytes of a bignum's value, without trailing zeros.
+fn to_bytes<T: Copy + Into<u64>>(digits: &[T]) -> Vec<u8> {
+    let width = std::mem::size_of::<T>();
+    let mut bytes: Vec<u8> =
+        digits.iter().flat_map(|&d| d.into().to_le_bytes()[..width].to_vec()).collect();
+    while bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+    bytes
+}
+
+#[test]
+pub fn test_mul_small_add() {
+    let value = |x: &Big| x.digits().iter().rev().fold(0u32, |v, &d| v << 8 | d as u32);
+    let cases = [(0, 10, 0), (0, 10, 7), (1, 0, 0xff), (0xffff, 0xff, 0xff), (0x1234, 10, 9)];
+    for &(a, b, c) in &cases {
+        let mut x = Big::from_u64(a);
+        x.mul_small_add(b, c);
+        assert_eq!(value(&x), a as u32 * b as u32 + c as u32, "{} * {} + {}", a, b, c);
+    }
+}
+
+#[test]
+pub fn test_64bit_digits() {
+    use dec2flt::bignum::{Big32x40, Big64x20};
+
+    assert_eq!(Big64x20::from_u64(u64::MAX).digits(), [u64::MAX]);
+    for &(a, b) in &[(1, 1), (u64::MAX, 3), (0x1234_5678_9abc_def0, u64::MAX), (7, 1 << 63)] {
+        let mut x = Big32x40::from_u64(a);
+        let mut y = Big64x20::from_u64(a);
+        x.mul_pow5(123).mul_digits(Big32x40::from_u64(b).digits()).mul_pow2(77).add_small(5);
+        y.mul_pow5(123).mul_digits(Big64x20::from_u64(b).digits()).mul_pow2(77).add_small(5);
+        assert_eq!(to_bytes(x.digits()), to_bytes(y.digits()));
+
+        let (mut q, mut r) = (Big32x40::from_small(0), Big32x40::from_small(0));
+        x.div_rem(&Big32x40::from_u64(b | 1 << 40), &mut q, &mut r);
+        let (mut q64, mut r64) = (Big64x20::from_small(0), Big64x20::from_small(0));
+        y.div_rem(&Big64x20::from_u64(b | 1 << 40), &mut q64, &mut r64);
+        assert_eq!(to_bytes(q.digits()), to_bytes(q64.digits()));
+        assert_eq!(to_bytes(r.digits()), to_bytes(r64.digits()));
+        assert_eq!(x.div_rem_small(1_000_000_007).1 as u64, y.div_rem_small(1_000_000_007).1);
+    }
+}
+
+#[test]
+pub fn test_large_pow5() {
+    use dec2flt::bignum::{Big64x100, LARGE_POW5, LARGE_POW5_E};
+
+    for (i, power) in LARGE_POW5.iter().enumerate() {
+        let mut expected = Big64x100::from_small(1);
+        for _ in 0..LARGE_POW5_E << i {
+            expected.mul_small(5);
+        }
+        assert_eq!(to_bytes(power), to_bytes(expected.digits()), "5^{}", LARGE_POW5_E << i);
+    }
+}
+
+#[test]
+pub fn test_mul_pow5() {
+    use dec2flt::bignum::{Big32x200, Big64x100};
+
+    for e in (0..2000).step_by(7).chain([26, 27, 28, 53, 54, 1700, 1727, 1728, 1755]) {
+        let mut expected = Big32x200::from_u64(0xdead_beef);
+        for _ in 0..e {
+            expected.mul_small(5);
+        }
+        let mut x = Big32x200::from_u64(0xdead_beef);
+        x.mul_pow5(e);
+        assert_eq!(x, expected, "5^{}", e);
+        let mut y = Big64x100::from_u64(0xdead_beef);
+        y.mul_pow5(e);
+        assert_eq!(to_bytes(y.digits()), to_bytes(expected.digits()), "5^{}", e);
+    }
+    for e in 0..10 {
+        let mut x = Big::from_small(1);
+        x.mul_pow5(e);
+        assert_eq!(x, Big::from_u64(5u64.pow(e as u32)));
+    }
+}
@@ -0,0 +1,52 @@
+use dec2flt::FloatErrorKind;
+
+/// The limits of a format with at most 16 bits, as bits of positive values.
+pub struct Format {
+    /// The largest finite value.
+    pub max: u16,
+    /// The value after the largest finite one, as if the exponent range went on.
+    pub next: f64,
+    /// What values that round past the largest finite one parse to.
+    pub overflow: Result<u16, FloatErrorKind>,
+    /// The sign bit, or zero if the format is unsigned.
+    pub sign_bit: u16,
+}
+
+/// The exact decimal value of `x`, which has fewer significant digits than this prints, even
+/// for the smallest numbers of the formats tested here.
+fn exact(x: f64) -> String {
+    format!("{:.160e}", x)
+}
+
+/// Parses the exact values of all finite numbers of a format, and the halfway points between
+/// them as well as numbers just below and above those, with both signs. `to_f64` converts the
+/// bits of positive numbers up to `format.max`.
+pub fn check_exhaustive<P, V>(parse: P, to_f64: V, format: Format)
+where
+    P: Fn(&str) -> Result<u16, FloatErrorKind>,
+    V: Fn(u16) -> f64,
+{
+    let check = |x: f64, expected: Result<u16, FloatErrorKind>| {
+        let s = exact(x);
+        assert_eq!(parse(&s), expected, "{}", s);
+        if format.sign_bit != 0 {
+            let negative = format!("-{}", s);
+            assert_eq!(parse(&negative), expected.map(|b| b | format.sign_bit), "{}", negative);
+        }
+    };
+    for bits in 0..=format.max {
+        let value = to_f64(bits);
+        check(value, Ok(bits));
+        // Look at the halfway point to the next value, and just below and above.
+        let (next, up) = if bits == format.max {
+            (format.next, format.overflow)
+        } else {
+            (to_f64(bits + 1), Ok(bits + 1))
+        };
+        let halfway = (value + next) / 2.0;
+        let nudge = (next - value) / 1024.0;
+        check(halfway, if bits % 2 == 0 { Ok(bits) } else { up });
+        check(halfway - nudge, Ok(bits));
+        check(halfway + nudge, up);
+    }
+}
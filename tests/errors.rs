@@ -0,0 +1,37 @@
+use dec2flt::{parse, parse_bytes, parse_partial, FloatErrorKind};
+
+fn error(s: &str) -> (FloatErrorKind, usize) {
+    let err = parse::<f64>(s).unwrap_err();
+    (*err.kind(), err.position())
+}
+
+#[test]
+pub fn test_error_kind() {
+    assert_eq!(error(""), (FloatErrorKind::Empty, 0));
+    assert_eq!(error("-"), (FloatErrorKind::EmptyMantissa, 1));
+    assert_eq!(error("."), (FloatErrorKind::EmptyMantissa, 1));
+    assert_eq!(error("+.e5"), (FloatErrorKind::EmptyMantissa, 2));
+    assert_eq!(error("e5"), (FloatErrorKind::EmptyMantissa, 0));
+    assert_eq!(error("a.12"), (FloatErrorKind::InvalidDigit, 0));
+    assert_eq!(error("-.x"), (FloatErrorKind::InvalidDigit, 2));
+    assert_eq!(error("1ex"), (FloatErrorKind::InvalidDigit, 2));
+    assert_eq!(error("1.5e+x"), (FloatErrorKind::InvalidDigit, 5));
+    assert_eq!(error("1e"), (FloatErrorKind::EmptyExponent, 2));
+    assert_eq!(error("-1.5E-"), (FloatErrorKind::EmptyExponent, 6));
+    assert_eq!(error("1.5x"), (FloatErrorKind::TrailingCharacters, 3));
+    assert_eq!(error("1..5"), (FloatErrorKind::TrailingCharacters, 2));
+    assert_eq!(error("1e5 "), (FloatErrorKind::TrailingCharacters, 3));
+    assert_eq!(error("1e999999999999999999999x"), (FloatErrorKind::TrailingCharacters, 23));
+    assert_eq!(error("-infinite"), (FloatErrorKind::TrailingCharacters, 4));
+}
+
+#[test]
+pub fn test_error_sources_agree() {
+    for s in ["", "-", "x", "1e", "1.5x"].iter() {
+        assert_eq!(parse::<f32>(s), parse_bytes::<f32>(s.as_bytes()));
+    }
+    let err = parse_partial::<f64>(b"-.x").unwrap_err();
+    assert_eq!((*err.kind(), err.position()), (FloatErrorKind::InvalidDigit, 2));
+    let err = parse::<f64>("1.5x").unwrap_err();
+    assert_eq!(err.to_string(), "trailing characters after float literal");
+}
@@ -0,0 +1,112 @@
+use dec2flt::{parse, parse_f128, F128};
+
+fn parse_bits(s: &str) -> u128 {
+    parse_f128(s).unwrap().to_bits()
+}
+
+/// The bits of the binary128 number with the same value as `x`.
+fn widen(x: f64) -> u128 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 63) as u128) << 127;
+    let exp = (bits >> 52 & 0x7ff) as i64;
+    let sig = bits & ((1 << 52) - 1);
+    let (exp, sig) = if exp == 0 {
+        if sig == 0 {
+            return sign;
+        }
+        // Subnormal `f64` are normal binary128 numbers.
+        let shift = sig.leading_zeros() as i64 - 11;
+        (1 - shift, (sig << shift) & ((1 << 52) - 1))
+    } else {
+        (exp, sig)
+    };
+    sign | ((exp - 1023 + 16383) as u128) << 112 | (sig as u128) << 60
+}
+
+#[test]
+pub fn test_parse_f128() {
+    assert_eq!(parse_bits("1"), 0x3fff << 112);
+    assert_eq!(parse_bits("-2.5"), 0xc000_4000 << 96);
+    assert_eq!(parse_bits("0.1"), 0x3ffb_9999_9999_9999_9999_9999_9999_999a);
+    let pi = "3.141592653589793238462643383279502884197";
+    assert_eq!(parse_bits(pi), 0x4000_921f_b544_42d1_8469_898c_c517_01b8);
+    assert_eq!(parse_bits("0.125"), 0x3ffc << 112);
+    assert_eq!(parse_bits("1234.567"), 0x4009_34a4_49ba_5e35_3f7c_ed91_6872_b021);
+    assert_eq!(parse_bits("0.001"), 0x3ff5_0624_dd2f_1a9f_be76_c8b4_3958_1062);
+    assert_eq!(parse_bits("9007199254740993"), 0x4034_0000_0000_0000_0800_0000_0000_0000);
+    let long = "123456789012345678901234567890123456789";
+    assert_eq!(parse_bits(long), 0x407d_7383_a695_8057_fb16_ab7e_8ca2_b8e6);
+    assert_eq!(parse_bits("1e4932"), 0x7ffe_ae59_6552_b8fd_ed99_d037_e3d0_4b75);
+    assert_eq!(parse_bits("1e-4951"), 0x8c75_6d96_9002);
+    assert_eq!(parse_bits("3.3621031431120935062626778173217526e-4932"), 1 << 112);
+    assert_eq!(parse_bits("6e-4966"), 1);
+    assert_eq!(parse_bits("2.5e-4966"), 0);
+    assert_eq!(parse_bits("1e-5000"), 0);
+    assert_eq!(parse_bits("1e5000"), F128::INFINITY.to_bits());
+    assert_eq!(parse_bits("-0"), 1 << 127);
+    assert_eq!(parse_bits("inf"), F128::INFINITY.to_bits());
+    assert_eq!(parse_bits("-Infinity"), F128::INFINITY.to_bits() | 1 << 127);
+    assert_eq!(parse_bits("nan"), F128::NAN.to_bits());
+}
+
+#[test]
+pub fn test_parse_f128_limits() {
+    // Just below and just above the smallest value that overflows to infinity.
+    let max = "1.18973149535723176508575932662800707347995686986910214150118685272271246896789\
+               80e4932";
+    assert_eq!(parse_bits(max), F128::MAX.to_bits());
+    let inf = "1.18973149535723176508575932662800707347995686986910214150118685272271246896789\
+               81e4932";
+    assert_eq!(parse_bits(inf), F128::INFINITY.to_bits());
+    // Just below and just above half the smallest subnormal.
+    let zero = "3.2375875597190125554622194791138232762497846690173405048449e-4966";
+    assert_eq!(parse_bits(zero), 0);
+    let tiny = "3.237587559719012555462219479113823276249784669017340504845e-4966";
+    assert_eq!(parse_bits(tiny), 1);
+}
+
+#[test]
+pub fn test_parse_f128_ties() {
+    // Exactly halfway between 1 and the next value, and between that and the one after.
+    let halfway = "1.00000000000000000000000000000000009629649721936179265279889712924636592690\
+                   508241076940976199693977832794189453125";
+    assert_eq!(parse_bits(halfway), 0x3fff << 112);
+    let above = format!("{}{}1", halfway, "0".repeat(12000));
+    assert_eq!(parse_bits(&above), 0x3fff << 112 | 1);
+    let halfway = "1.00000000000000000000000000000000028888949165808537795839669138773909778071\
+                   524723230822928599081933498382568359375";
+    assert_eq!(parse_bits(halfway), 0x3fff << 112 | 2);
+    let below = format!("{}4{}", &halfway[..halfway.len() - 1], "9".repeat(12000));
+    assert_eq!(parse_bits(&below), 0x3fff << 112 | 1);
+    // Halfway between integers, which need all digits, however they are written.
+    let halfway = "10384593717069655257060992658440193";
+    assert_eq!(parse_bits(halfway), 0x4070 << 112);
+    assert_eq!(parse_bits(&format!("{}.00", halfway)), 0x4070 << 112);
+    assert_eq!(parse_bits("1038459371706965525706099265844019.3e1"), 0x4070 << 112);
+    assert_eq!(parse_bits("10384593717069655257060992658440195"), 0x4070 << 112 | 2);
+}
+
+#[test]
+pub fn test_parse_f128_from_f64() {
+    // Every `f64` is a binary128 number, so its exact decimal value must come back unchanged.
+    let mut bits = 1u64;
+    while bits < 0x7ff0_0000_0000_0000 {
+        let x = f64::from_bits(bits);
+        let s = format!("{:.800e}", x);
+        assert_eq!(parse_bits(&s), widen(x), "{}", s);
+        assert_eq!(parse_bits(&format!("-{}", s)), widen(-x), "{}", s);
+        bits += 0x0006_8db8_bac7_10cb;
+    }
+    for &x in [f64::MAX, f64::MIN_POSITIVE, f64::MIN_POSITIVE / 3.0, 1e-320, 5e-324].iter() {
+        assert_eq!(parse_bits(&format!("{:.800e}", x)), widen(x), "{:e}", x);
+    }
+}
+
+#[test]
+pub fn test_parse_f128_errors() {
+    // The syntax is the same as for the other types, and so are the errors.
+    let inputs = ["", "-", "+.", "1e", "1.5x", "-infx", "nanx", "1.5\u{e9}", "0x1p3"];
+    for input in inputs.iter() {
+        assert_eq!(parse_f128(input).unwrap_err(), parse::<f64>(input).unwrap_err(), "{}", input);
+    }
+}
@@ -0,0 +1,60 @@
+mod common;
+
+use common::{check_exhaustive, Format};
+use dec2flt::{parse, F16};
+
+fn parse_bits(s: &str) -> u16 {
+    parse::<F16>(s).unwrap().to_bits()
+}
+
+#[test]
+pub fn test_parse_f16() {
+    assert_eq!(parse_bits("1"), 0x3c00);
+    assert_eq!(parse_bits("-2.5"), 0xc100);
+    assert_eq!(parse_bits("3.14159"), 0x4248);
+    assert_eq!(parse_bits("0.1"), 0x2e66);
+    assert_eq!(parse_bits("1e4"), 0x70e2);
+    assert_eq!(parse_bits("65504"), F16::MAX.to_bits());
+    assert_eq!(parse_bits("65519.999"), F16::MAX.to_bits());
+    assert_eq!(parse_bits("65520"), F16::INFINITY.to_bits());
+    assert_eq!(parse_bits("1e5"), F16::INFINITY.to_bits());
+    assert_eq!(parse_bits("6.103515625e-5"), F16::MIN_POSITIVE.to_bits());
+    assert_eq!(parse_bits("0.000060975551605224609375"), 0x03ff);
+    assert_eq!(parse_bits("5.9604644775390625e-8"), 0x0001);
+    // Exactly half of the smallest subnormal is a tie with zero.
+    assert_eq!(parse_bits("2.98023223876953125e-8"), 0x0000);
+    assert_eq!(parse_bits("2.98023223876953125000001e-8"), 0x0001);
+    assert_eq!(parse_bits("1e-10"), 0x0000);
+    assert_eq!(parse_bits("-0"), 0x8000);
+    assert_eq!(parse_bits("inf"), 0x7c00);
+    assert_eq!(parse_bits("-inf"), 0xfc00);
+    assert_eq!(parse_bits("NaN"), F16::NAN.to_bits());
+}
+
+#[test]
+pub fn test_parse_f16_exhaustive() {
+    let parse = |s: &str| parse::<F16>(s).map(|x| x.to_bits()).map_err(|e| *e.kind());
+    let to_f64 = |bits| F16::from_bits(bits).to_f32() as f64;
+    let format = Format {
+        max: F16::MAX.to_bits(),
+        next: 65536.0,
+        overflow: Ok(F16::INFINITY.to_bits()),
+        sign_bit: 0x8000,
+    };
+    check_exhaustive(parse, to_f64, format);
+}
+
+#[test]
+pub fn test_f16_from_f32() {
+    for bits in 0..=u16::MAX {
+        let x = F16::from_bits(bits);
+        assert_eq!(F16::from_f32(x.to_f32()).to_bits(), bits);
+    }
+    assert_eq!(F16::from_f32(1.0 + 1.0 / 2048.0).to_bits(), 0x3c00);
+    assert_eq!(F16::from_f32(1.0 + 3.0 / 2048.0).to_bits(), 0x3c02);
+    assert_eq!(F16::from_f32(65520.0).to_bits(), 0x7c00);
+    assert_eq!(F16::from_f32(1e-10).to_bits(), 0x0000);
+    assert_eq!(F16::from_f32(f32::MAX).to_bits(), 0x7c00);
+    assert_eq!(F16::from_f32(-f32::INFINITY).to_bits(), 0xfc00);
+    assert!(F16::from_f32(f32::from_bits(0x7f80_0001)).to_f32().is_nan());
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::{check_exhaustive, Format};
+use dec2flt::FloatErrorKind::{self, OutOfRange};
+use dec2flt::OverflowPolicy::{self, Error, Infinity, Nan, Saturate};
+use dec2flt::{parse, parse_e4m3fn, parse_e5m2, parse_e8m0, E4M3FN, E5M2, E8M0};
+
+fn e4m3fn(s: &str, overflow: OverflowPolicy) -> Result<u8, FloatErrorKind> {
+    parse_e4m3fn(s, overflow).map(|x| x.to_bits()).map_err(|e| *e.kind())
+}
+
+fn e5m2(s: &str, overflow: OverflowPolicy) -> Result<u8, FloatErrorKind> {
+    parse_e5m2(s, overflow).map(|x| x.to_bits()).map_err(|e| *e.kind())
+}
+
+fn e8m0(s: &str, overflow: OverflowPolicy) -> Result<u8, FloatErrorKind> {
+    parse_e8m0(s, overflow).map(|x| x.to_bits()).map_err(|e| *e.kind())
+}
+
+#[test]
+pub fn test_parse_e4m3fn() {
+    assert_eq!(e4m3fn("1", Error), Ok(0x38));
+    assert_eq!(e4m3fn("-0.1", Error), Ok(0x9d));
+    assert_eq!(e4m3fn("-0", Error), Ok(0x80));
+    assert_eq!(e4m3fn("0.001953125", Error), Ok(0x01));
+    assert_eq!(e4m3fn("0.0009765625", Error), Ok(0x00));
+    assert_eq!(e4m3fn("0.00097656250001", Error), Ok(0x01));
+    assert_eq!(e4m3fn("1e-100", Error), Ok(0x00));
+    assert_eq!(e4m3fn("-1e-99999999999999999999", Error), Ok(0x80));
+    assert_eq!(e4m3fn("nan", Error), Ok(0x7f));
+    assert_eq!(e4m3fn("-nan", Error), Ok(0xff));
+    // Halfway between 448 and 480, which would be the NaN, so this rounds down to even.
+    assert_eq!(e4m3fn("464", Error), Ok(0x7e));
+    assert_eq!(e4m3fn("-464", Error), Ok(0xfe));
+    for s in ["464.0001", "1e10", "1e99999", "inf"].iter() {
+        assert_eq!(e4m3fn(s, Saturate), Ok(0x7e), "{}", s);
+        assert_eq!(e4m3fn(s, Infinity), Ok(0x7f), "{}", s);
+        assert_eq!(e4m3fn(s, Nan), Ok(0x7f), "{}", s);
+        assert_eq!(e4m3fn(s, Error), Err(FloatErrorKind::OutOfRange), "{}", s);
+        let negative = format!("-{}", s);
+        assert_eq!(e4m3fn(&negative, Saturate), Ok(0xfe), "{}", negative);
+        assert_eq!(e4m3fn(&negative, Nan), Ok(0xff), "{}", negative);
+    }
+}
+
+#[test]
+pub fn test_parse_e5m2() {
+    assert_eq!(e5m2("1", Error), Ok(0x3c));
+    assert_eq!(e5m2("-0.1", Error), Ok(0xae));
+    assert_eq!(e5m2("0.0000152587890625", Error), Ok(0x01));
+    assert_eq!(e5m2("57344", Error), Ok(0x7b));
+    assert_eq!(e5m2("61439.999", Error), Ok(0x7b));
+    assert_eq!(e5m2("inf", Error), Ok(0x7c));
+    assert_eq!(e5m2("-infinity", Saturate), Ok(0xfc));
+    assert_eq!(e5m2("NaN", Saturate), Ok(0x7e));
+    // Halfway between 57344 and 65536, which is even, so this overflows.
+    for s in ["61440", "1e10"].iter() {
+        assert_eq!(e5m2(s, Saturate), Ok(0x7b), "{}", s);
+        assert_eq!(e5m2(s, Infinity), Ok(0x7c), "{}", s);
+        assert_eq!(e5m2(s, Nan), Ok(0x7e), "{}", s);
+        assert_eq!(e5m2(s, Error), Err(FloatErrorKind::OutOfRange), "{}", s);
+        let negative = format!("-{}", s);
+        assert_eq!(e5m2(&negative, Saturate), Ok(0xfb), "{}", negative);
+        assert_eq!(e5m2(&negative, Infinity), Ok(0xfc), "{}", negative);
+    }
+}
+
+#[test]
+pub fn test_parse_e8m0() {
+    assert_eq!(e8m0("1", Error), Ok(0x7f));
+    assert_eq!(e8m0("0.25", Error), Ok(0x7d));
+    assert_eq!(e8m0("5.877471754111438e-39", Error), Ok(0x00));
+    assert_eq!(e8m0("1e-100", Error), Ok(0x00));
+    assert_eq!(e8m0("1e-99999999999999999999", Error), Ok(0x00));
+    assert_eq!(e8m0("1.7014118346046923e38", Error), Ok(0xfe));
+    assert_eq!(e8m0("nan", Error), Ok(0xff));
+    assert_eq!(e8m0("-nan", Error), Ok(0xff));
+    // Ties between two powers of two go to the even exponent.
+    assert_eq!(e8m0("1.5", Error), Ok(0x80));
+    assert_eq!(e8m0("1.4999999", Error), Ok(0x7f));
+    assert_eq!(e8m0("3", Error), Ok(0x80));
+    assert_eq!(e8m0("3.0000001", Error), Ok(0x81));
+    assert_eq!(e8m0("0.75", Error), Ok(0x7e));
+    assert_eq!(e8m0("255211775190703847597530955573826158592", Error), Ok(0xfe));
+    for s in ["255211775190703847597530955573826158593", "1e39", "inf"].iter() {
+        assert_eq!(e8m0(s, Saturate), Ok(0xfe), "{}", s);
+        assert_eq!(e8m0(s, Infinity), Ok(0xff), "{}", s);
+        assert_eq!(e8m0(s, Nan), Ok(0xff), "{}", s);
+        assert_eq!(e8m0(s, Error), Err(FloatErrorKind::OutOfRange), "{}", s);
+    }
+    // Neither zero nor negative numbers can be represented.
+    for s in ["0", "-0", "-1", "-1e-100", "-inf"].iter() {
+        assert_eq!(e8m0(s, Saturate), Ok(0x00), "{}", s);
+        assert_eq!(e8m0(s, Infinity), Ok(0xff), "{}", s);
+        assert_eq!(e8m0(s, Error), Err(FloatErrorKind::OutOfRange), "{}", s);
+    }
+}
+
+#[test]
+pub fn test_parse_fp8_exhaustive() {
+    let parse = |s: &str| e4m3fn(s, Error).map(u16::from);
+    let to_f64 = |bits| E4M3FN::from_bits(bits as u8).to_f32() as f64;
+    let format = Format { max: 0x7e, next: 480.0, overflow: Err(OutOfRange), sign_bit: 0x80 };
+    check_exhaustive(parse, to_f64, format);
+    let parse = |s: &str| e5m2(s, Error).map(u16::from);
+    let to_f64 = |bits| E5M2::from_bits(bits as u8).to_f32() as f64;
+    let format = Format { max: 0x7b, next: 65536.0, overflow: Err(OutOfRange), sign_bit: 0x80 };
+    check_exhaustive(parse, to_f64, format);
+    // Negative numbers can't be represented, so they aren't checked.
+    let parse = |s: &str| e8m0(s, Error).map(u16::from);
+    let to_f64 = |bits| E8M0::from_bits(bits as u8).to_f32() as f64;
+    let format = Format { max: 0xfe, next: 2f64.powi(128), overflow: Err(OutOfRange), sign_bit: 0 };
+    check_exhaustive(parse, to_f64, format);
+}
+
+#[test]
+pub fn test_parse_fp8_errors() {
+    for input in ["", "-", "1e", "1.5x", "infx"].iter() {
+        let expected = parse::<f64>(input).unwrap_err();
+        assert_eq!(parse_e4m3fn(input, Saturate).unwrap_err(), expected);
+        assert_eq!(parse_e5m2(input, Saturate).unwrap_err(), expected);
+        assert_eq!(parse_e8m0(input, Saturate).unwrap_err(), expected);
+    }
+}
@@ -0,0 +1,57 @@
+use dec2flt::{parse_hex, parse_partial_with_options, parse_with_options};
+use dec2flt::{FloatErrorKind, ParseOptions};
+
+#[test]
+pub fn test_parse_hex() {
+    assert_eq!(parse_hex::<f64>(b"0x1.8p3").unwrap(), 12.0);
+    assert_eq!(parse_hex::<f64>(b"0X.8P1").unwrap(), 1.0);
+    assert_eq!(parse_hex::<f64>(b"0xA").unwrap(), 10.0);
+    assert_eq!(parse_hex::<f64>(b"0x1.fffffffffffffp+1023").unwrap(), f64::MAX);
+    assert_eq!(parse_hex::<f64>(b"-0x1p-1074").unwrap(), -5e-324);
+    assert_eq!(parse_hex::<f64>(b"0x1p-1022").unwrap(), f64::MIN_POSITIVE);
+    assert_eq!(parse_hex::<f32>(b"0x1.fffffep127").unwrap(), f32::MAX);
+    assert_eq!(parse_hex::<f64>(&format!("0x1{}p-400", "0".repeat(100)).as_bytes()).unwrap(), 1.0);
+    assert_eq!(parse_hex::<f64>(b"0x0p99999999999999999999").unwrap(), 0.0);
+    assert_eq!(parse_hex::<f64>(b"-inf").unwrap(), f64::NEG_INFINITY);
+}
+
+#[test]
+pub fn test_parse_hex_rounding() {
+    // Ties go to even, anything past the tie rounds up.
+    assert_eq!(parse_hex::<f32>(b"0x1.000001p0").unwrap(), 1.0);
+    assert_eq!(parse_hex::<f32>(b"0x1.000003p0").unwrap(), 1.0000002);
+    assert_eq!(parse_hex::<f32>(b"0x1.0000010000000000000001p0").unwrap(), 1.0000001);
+    assert_eq!(parse_hex::<f64>(b"0x1.fffffffffffff7ffffffp1023").unwrap(), f64::MAX);
+    assert_eq!(parse_hex::<f64>(b"0x1.fffffffffffff8p1023").unwrap(), f64::INFINITY);
+    // Subnormals have fewer significand bits to round to.
+    assert_eq!(parse_hex::<f64>(b"0x1p-1075").unwrap(), 0.0);
+    assert_eq!(parse_hex::<f64>(b"0x1.00000000000000000001p-1075").unwrap(), 5e-324);
+    assert_eq!(parse_hex::<f64>(b"0x1.8p-1074").unwrap(), 1e-323);
+    assert_eq!(parse_hex::<f64>(b"0x1.ffffffffffffffp-1023").unwrap(), f64::MIN_POSITIVE);
+    assert_eq!(parse_hex::<f64>(b"0x1p-1076").unwrap(), 0.0);
+}
+
+#[test]
+pub fn test_parse_hex_invalid() {
+    let error = |s: &[u8]| {
+        let err = parse_hex::<f64>(s).unwrap_err();
+        (*err.kind(), err.position())
+    };
+    assert_eq!(error(b"1.5"), (FloatErrorKind::InvalidDigit, 0));
+    assert_eq!(error(b"-0x"), (FloatErrorKind::EmptyMantissa, 3));
+    assert_eq!(error(b"0x.p1"), (FloatErrorKind::EmptyMantissa, 3));
+    assert_eq!(error(b"0xg"), (FloatErrorKind::InvalidDigit, 2));
+    assert_eq!(error(b"0x1P"), (FloatErrorKind::EmptyExponent, 4));
+    assert_eq!(error(b"0x1p1.5"), (FloatErrorKind::TrailingCharacters, 5));
+}
+
+#[test]
+pub fn test_hex_option() {
+    let options = ParseOptions::new().hex(true);
+    assert_eq!(parse_with_options::<f64>(b"0x1e5p1", &options).unwrap(), 970.0);
+    assert_eq!(parse_with_options::<f64>(b"1e5", &options).unwrap(), 1e5);
+    assert!(parse_with_options::<f64>(b"0x1p3", &ParseOptions::new()).is_err());
+    assert_eq!(parse_partial_with_options::<f64>(b"0x1.8p3junk", &options).unwrap(), (12.0, 7));
+    assert_eq!(parse_partial_with_options::<f64>(b"-0xg", &options).unwrap(), (-0.0, 2));
+    assert_eq!(parse_partial_with_options::<f64>(b"0x1p+", &options).unwrap(), (1.0, 3));
+}
@@ -0,0 +1,40 @@
+use dec2flt::{parse_json, FloatErrorKind};
+
+fn error_of(s: &str) -> (FloatErrorKind, usize) {
+    let e = parse_json::<f64>(s.as_bytes()).unwrap_err();
+    (*e.kind(), e.position())
+}
+
+#[test]
+pub fn test_parse_json() {
+    assert_eq!(parse_json::<f64>(b"0"), Ok(0.0));
+    assert_eq!(parse_json::<f64>(b"-0").map(f64::is_sign_negative), Ok(true));
+    assert_eq!(parse_json::<f64>(b"10"), Ok(10.0));
+    assert_eq!(parse_json::<f64>(b"0.25"), Ok(0.25));
+    assert_eq!(parse_json::<f64>(b"-1.5E-3"), Ok(-0.0015));
+    assert_eq!(parse_json::<f64>(b"0e+5"), Ok(0.0));
+    assert_eq!(parse_json::<f32>(b"1e400"), Ok(f32::INFINITY));
+    assert_eq!(parse_json::<f64>(b"2.2250738585072011e-308"), Ok(2.225073858507201e-308));
+}
+
+#[test]
+pub fn test_parse_json_invalid() {
+    use FloatErrorKind::*;
+    assert_eq!(error_of(""), (Empty, 0));
+    assert_eq!(error_of("-"), (EmptyMantissa, 1));
+    assert_eq!(error_of("+1"), (InvalidDigit, 0));
+    assert_eq!(error_of(".5"), (InvalidDigit, 0));
+    assert_eq!(error_of("-.5"), (InvalidDigit, 1));
+    assert_eq!(error_of("5."), (EmptyMantissa, 2));
+    assert_eq!(error_of("5.e3"), (InvalidDigit, 2));
+    assert_eq!(error_of("007"), (InvalidDigit, 1));
+    assert_eq!(error_of("-00.5"), (InvalidDigit, 2));
+    assert_eq!(error_of("1e"), (EmptyExponent, 2));
+    assert_eq!(error_of("1e+-1"), (InvalidDigit, 3));
+    assert_eq!(error_of("1.5x"), (TrailingCharacters, 3));
+    assert_eq!(error_of("1 "), (TrailingCharacters, 1));
+    assert_eq!(error_of("inf"), (InvalidDigit, 0));
+    assert_eq!(error_of("-Infinity"), (InvalidDigit, 1));
+    assert_eq!(error_of("NaN"), (InvalidDigit, 0));
+    assert_eq!(error_of("0x10"), (TrailingCharacters, 1));
+}
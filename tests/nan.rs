@@ -0,0 +1,74 @@
+use dec2flt::{parse, parse_partial_with_options, parse_with_options, strtod};
+use dec2flt::{FloatErrorKind, ParseOptions};
+
+const PAYLOADS: ParseOptions = ParseOptions::new().nan_payloads(true);
+
+fn bits64(s: &str, options: &ParseOptions) -> Result<u64, (FloatErrorKind, usize)> {
+    match parse_with_options::<f64>(s.as_bytes(), options) {
+        Ok(x) => Ok(x.to_bits()),
+        Err(e) => Err((*e.kind(), e.position())),
+    }
+}
+
+fn bits32(s: &str, options: &ParseOptions) -> Result<u32, (FloatErrorKind, usize)> {
+    match parse_with_options::<f32>(s.as_bytes(), options) {
+        Ok(x) => Ok(x.to_bits()),
+        Err(e) => Err((*e.kind(), e.position())),
+    }
+}
+
+#[test]
+pub fn test_nan_payload() {
+    assert_eq!(bits64("nan", &PAYLOADS), Ok(0x7ff8000000000000));
+    assert_eq!(bits64("nan(0x1234)", &PAYLOADS), Ok(0x7ff8000000001234));
+    assert_eq!(bits64("NaN(1234)", &PAYLOADS), Ok(0x7ff80000000004d2));
+    assert_eq!(bits64("nan()", &PAYLOADS), Ok(0x7ff8000000000000));
+    assert_eq!(bits64("-nan(0X7ffffffffffff)", &PAYLOADS), Ok(0xffffffffffffffff));
+    assert_eq!(bits32("nan(0x3fffff)", &PAYLOADS), Ok(0x7fffffff));
+    assert_eq!(bits64("snan", &PAYLOADS), Ok(0x7ff4000000000000));
+    assert_eq!(bits64("SNAN(1)", &PAYLOADS), Ok(0x7ff0000000000001));
+    assert_eq!(bits64("-snan(0)", &PAYLOADS), Ok(0xfff4000000000000));
+    assert_eq!(bits32("snan(0x2a)", &PAYLOADS), Ok(0x7f80002a));
+    assert_eq!(bits64("inf", &PAYLOADS), Ok(f64::INFINITY.to_bits()));
+    assert_eq!(parse_partial_with_options::<f64>(b"nan(12", &PAYLOADS).unwrap().1, 3);
+    let (x, len) = parse_partial_with_options::<f64>(b"snan(0x5),", &PAYLOADS).unwrap();
+    assert_eq!((x.to_bits(), len), (0x7ff0000000000005, 9));
+}
+
+#[test]
+pub fn test_nan_payload_invalid() {
+    use FloatErrorKind::*;
+    assert_eq!(bits64("nan(0x8000000000000)", &PAYLOADS), Err((InvalidNan, 4)));
+    assert_eq!(bits64("-snan(99999999999999999999)", &PAYLOADS), Err((InvalidNan, 6)));
+    assert_eq!(bits32("nan(0x400000)", &PAYLOADS), Err((InvalidNan, 4)));
+    assert_eq!(bits64("nan(0x)", &PAYLOADS), Err((TrailingCharacters, 3)));
+    assert_eq!(bits64("nan(abc)", &PAYLOADS), Err((TrailingCharacters, 3)));
+    assert_eq!(bits64("nan(12", &PAYLOADS), Err((TrailingCharacters, 3)));
+    // Without the option, none of this is accepted.
+    assert_eq!(bits64("nan(1)", &ParseOptions::new()), Err((TrailingCharacters, 3)));
+    assert_eq!(bits64("snan", &ParseOptions::new()), Err((InvalidDigit, 0)));
+}
+
+#[test]
+pub fn test_signed_nan() {
+    let unsigned = ParseOptions::new().signed_nan(false);
+    assert!(parse::<f64>("-nan").unwrap().is_sign_negative());
+    assert!(parse_with_options::<f64>(b"nan", &unsigned).unwrap().is_nan());
+    assert_eq!(bits64("-nan", &unsigned), Err((FloatErrorKind::InvalidNan, 0)));
+    assert_eq!(bits64("+NaN", &unsigned), Err((FloatErrorKind::InvalidNan, 0)));
+    assert_eq!(bits64("-snan", &PAYLOADS.signed_nan(false)), Err((FloatErrorKind::InvalidNan, 0)));
+    assert_eq!(bits64("-inf", &unsigned), Ok(f64::NEG_INFINITY.to_bits()));
+    assert!(parse_partial_with_options::<f64>(b"-nan", &unsigned).is_err());
+}
+
+#[test]
+pub fn test_strtod_nan_payload() {
+    // glibc takes the payload from C integers, and cuts off what doesn't fit.
+    let bits = |s: &str| strtod::<f64>(s.as_bytes()).value.to_bits();
+    assert_eq!(bits("nan(0x1234)"), 0x7ff8000000001234);
+    assert_eq!(bits("nan(010)"), 0x7ff8000000000008);
+    assert_eq!(bits("nan(08)"), 0x7ff8000000000000);
+    assert_eq!(bits("nan(0x8000000000001)"), 0x7ff8000000000001);
+    assert_eq!(bits("-nan(0xfffffffffffffffffff)"), 0xffffffffffffffff);
+    assert_eq!(strtod::<f32>(b"nan(1234)").value.to_bits(), 0x7fc004d2);
+}
@@ -0,0 +1,56 @@
+use dec2flt::parse_partial;
+
+#[test]
+pub fn test_parse_partial() {
+    assert_eq!(parse_partial::<f64>(b"1.2345e22").unwrap(), (1.2345e22, 9));
+    assert_eq!(parse_partial::<f64>(b"1e").unwrap(), (1.0, 1));
+    assert_eq!(parse_partial::<f64>(b"1.5e+x").unwrap(), (1.5, 3));
+    assert_eq!(parse_partial::<f64>(b"-12.5e-1, 3").unwrap(), (-1.25, 8));
+    assert_eq!(parse_partial::<f64>(b"+.5.5").unwrap(), (0.5, 3));
+    assert_eq!(parse_partial::<f64>(b"5.x").unwrap(), (5.0, 2));
+    assert_eq!(parse_partial::<f32>(b"1e400 ").unwrap(), (f32::INFINITY, 5));
+    assert_eq!(parse_partial::<f64>(b"1e-99999999999999999999;").unwrap(), (0.0, 23));
+}
+
+#[test]
+pub fn test_parse_partial_special() {
+    assert_eq!(parse_partial::<f64>(b"inf").unwrap(), (f64::INFINITY, 3));
+    assert_eq!(parse_partial::<f64>(b"-Infinity!").unwrap(), (f64::NEG_INFINITY, 9));
+    assert_eq!(parse_partial::<f64>(b"INFINIT").unwrap(), (f64::INFINITY, 3));
+    let (nan, len) = parse_partial::<f64>(b"nanny").unwrap();
+    assert!(nan.is_nan());
+    assert_eq!(len, 3);
+}
+
+#[test]
+pub fn test_parse_partial_invalid() {
+    assert!(parse_partial::<f64>(b"").is_err());
+    assert!(parse_partial::<f64>(b"-").is_err());
+    assert!(parse_partial::<f64>(b".").is_err());
+    assert!(parse_partial::<f64>(b".e1").is_err());
+    assert!(parse_partial::<f64>(b"e1").is_err());
+    assert!(parse_partial::<f64>(b" 1").is_err());
+    assert!(parse_partial::<f64>(b"+-1").is_err());
+}
+
+#[test]
+pub fn test_parse_partial_digit_runs() {
+    // The end of a digit run must be found no matter where it is relative to the chunks that are
+    // scanned together, and bytes close to the digits or with the high bit set don't count.
+    let digits = b"12345678901234567890123456789012345678901234567890";
+    for n in 1..digits.len() {
+        for &c in b"/: a\x80\xb0\xb9\xff".iter() {
+            let mut s = digits[..n].to_vec();
+            s.push(c);
+            s.extend_from_slice(digits);
+            let (_, len) = parse_partial::<f64>(&s).unwrap();
+            assert_eq!(len, n, "{:?}", s);
+            let mut s = b"0.".to_vec();
+            s.extend_from_slice(&digits[..n]);
+            s.push(c);
+            s.extend_from_slice(digits);
+            let (_, len) = parse_partial::<f64>(&s).unwrap();
+            assert_eq!(len, n + 2, "{:?}", s);
+        }
+    }
+}
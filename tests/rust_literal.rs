@@ -0,0 +1,57 @@
+use dec2flt::{parse_rust_literal, parse_rust_literal_as};
+use dec2flt::{FloatErrorKind, FloatSuffix, RustFloat};
+
+fn error_of(s: &str) -> (FloatErrorKind, usize) {
+    let e = parse_rust_literal(s.as_bytes()).unwrap_err();
+    (*e.kind(), e.position())
+}
+
+#[test]
+pub fn test_parse_rust_literal() {
+    use FloatSuffix::{F32, F64};
+    let parse = |s: &str| parse_rust_literal(s.as_bytes()).unwrap();
+    assert_eq!(parse("1_000.5f32"), (RustFloat::F32(1000.5), Some(F32)));
+    assert_eq!(parse("2e10_f64"), (RustFloat::F64(2e10), Some(F64)));
+    assert_eq!(parse("0.1E-3"), (RustFloat::F64(0.1E-3), None));
+    assert_eq!(parse("1."), (RustFloat::F64(1.), None));
+    assert_eq!(parse("5f32"), (RustFloat::F32(5f32), Some(F32)));
+    assert_eq!(parse("1__0_f64"), (RustFloat::F64(1__0_f64), Some(F64)));
+    assert_eq!(parse("1_.5_"), (RustFloat::F64(1_.5_), None));
+    assert_eq!(parse("1e_5"), (RustFloat::F64(1e_5), None));
+    assert_eq!(parse("1E+_5_"), (RustFloat::F64(1E+_5_), None));
+    assert_eq!(parse("007.5"), (RustFloat::F64(007.5), None));
+    assert_eq!(parse("0.1f32"), (RustFloat::F32(0.1), Some(F32)));
+    assert_eq!(parse("1e400"), (RustFloat::F64(f64::INFINITY), None));
+}
+
+#[test]
+pub fn test_parse_rust_literal_as() {
+    // The value is converted to the requested type, regardless of the suffix.
+    assert_eq!(parse_rust_literal_as::<f64>(b"0.1f32"), Ok((0.1, Some(FloatSuffix::F32))));
+    assert_eq!(parse_rust_literal_as::<f32>(b"1e40"), Ok((f32::INFINITY, None)));
+}
+
+#[test]
+pub fn test_parse_rust_literal_invalid() {
+    use FloatErrorKind::*;
+    assert_eq!(error_of(""), (Empty, 0));
+    assert_eq!(error_of(".5"), (InvalidDigit, 0));
+    assert_eq!(error_of("-1.5"), (InvalidDigit, 0));
+    assert_eq!(error_of("_1.5"), (InvalidDigitSeparator, 0));
+    assert_eq!(error_of("1.e5"), (TrailingCharacters, 2));
+    assert_eq!(error_of("1.f32"), (TrailingCharacters, 2));
+    assert_eq!(error_of("1._5"), (InvalidDigitSeparator, 2));
+    assert_eq!(error_of("1..5"), (TrailingCharacters, 2));
+    assert_eq!(error_of("1.5.0"), (TrailingCharacters, 3));
+    assert_eq!(error_of("5"), (InvalidSuffix, 1));
+    assert_eq!(error_of("1_000"), (InvalidSuffix, 5));
+    assert_eq!(error_of("1.5u8"), (InvalidSuffix, 3));
+    assert_eq!(error_of("1.5f16"), (InvalidSuffix, 3));
+    assert_eq!(error_of("1.5F32"), (InvalidSuffix, 3));
+    assert_eq!(error_of("1e"), (EmptyExponent, 2));
+    assert_eq!(error_of("1e_"), (InvalidDigitSeparator, 2));
+    assert_eq!(error_of("1ef32"), (InvalidDigit, 2));
+    assert_eq!(error_of("0x1f32"), (InvalidDigit, 1));
+    assert_eq!(error_of("0b1f32"), (InvalidDigit, 1));
+    assert_eq!(error_of("1.5 "), (TrailingCharacters, 3));
+}
@@ -0,0 +1,71 @@
+use dec2flt::{parse_partial_with_options, parse_with_options};
+use dec2flt::{FloatErrorKind, ParseOptions, SeparatorPlacement};
+
+const INNER: ParseOptions = ParseOptions::new().digit_separator(Some(b'_'));
+const RUST: ParseOptions = INNER.separator_placement(SeparatorPlacement::RUST);
+
+fn error_of(s: &str, options: &ParseOptions) -> (FloatErrorKind, usize) {
+    let e = parse_with_options::<f64>(s.as_bytes(), options).unwrap_err();
+    (*e.kind(), e.position())
+}
+
+#[test]
+pub fn test_parse_separators() {
+    assert_eq!(parse_with_options::<f64>(b"1_000_000.000_1", &INNER), Ok(1000000.0001));
+    assert_eq!(parse_with_options::<f64>(b"-1_0e1_0", &INNER), Ok(-1e11));
+    assert_eq!(parse_with_options::<f64>(b".5_5", &INNER), Ok(0.55));
+    assert_eq!(parse_with_options::<f64>(b"1__0_.5_", &RUST), Ok(10.5));
+    assert_eq!(parse_with_options::<f64>(b"1e5__", &RUST), Ok(1e5));
+    assert_eq!(parse_with_options::<f64>(b"1e0_0_3_0_8", &INNER), Ok(1e308));
+    assert_eq!(parse_with_options::<f64>(b"1e1_2345_6789", &INNER), Ok(f64::INFINITY));
+    let leading = INNER.separator_placement(SeparatorPlacement::new().leading(true));
+    assert_eq!(parse_with_options::<f64>(b"_1._5e_1", &leading), Ok(15.0));
+    let cpp = ParseOptions::new().digit_separator(Some(b'\''));
+    assert_eq!(parse_with_options::<f32>(b"1'048'576", &cpp), Ok(1048576.0));
+    let hex = INNER.hex(true);
+    assert_eq!(parse_with_options::<f64>(b"0x1_0.8p1_0", &hex), Ok(16896.0));
+    // Separators are not accepted unless enabled.
+    assert!(parse_with_options::<f64>(b"1_000", &ParseOptions::new()).is_err());
+}
+
+#[test]
+pub fn test_parse_separators_many_digits() {
+    // Separators don't count as digits, so they can't push significant digits out of the
+    // window that decides the rounding.
+    let halfway = "9007199254740993"; // 2^53 + 1, exactly halfway between two f64 values.
+    let s = format!("{}.{}1", halfway, "0_".repeat(2000));
+    assert_eq!(parse_with_options::<f64>(s.as_bytes(), &INNER), Ok(9007199254740994.0));
+    let s = format!("{}.{}0", halfway, "0_".repeat(2000));
+    assert_eq!(parse_with_options::<f64>(s.as_bytes(), &INNER), Ok(9007199254740992.0));
+    let s = format!("0.{}1e1_0", "0_".repeat(2000));
+    assert_eq!(parse_with_options::<f64>(s.as_bytes(), &INNER), Ok(1e-1991));
+    let s = format!("1{}e-1_000", "_0".repeat(1000));
+    assert_eq!(parse_with_options::<f64>(s.as_bytes(), &INNER), Ok(1.0));
+    let s = format!("1e{}1", "0_".repeat(30));
+    assert_eq!(parse_with_options::<f64>(s.as_bytes(), &INNER), Ok(10.0));
+}
+
+#[test]
+pub fn test_parse_separators_invalid() {
+    use FloatErrorKind::*;
+    assert_eq!(error_of("1__000", &INNER), (InvalidDigitSeparator, 1));
+    assert_eq!(error_of("_1", &INNER), (InvalidDigitSeparator, 0));
+    assert_eq!(error_of("1_", &INNER), (InvalidDigitSeparator, 1));
+    assert_eq!(error_of("1_.5", &INNER), (InvalidDigitSeparator, 1));
+    assert_eq!(error_of("1._5", &INNER), (InvalidDigitSeparator, 2));
+    assert_eq!(error_of("1.5_e3", &INNER), (InvalidDigitSeparator, 3));
+    assert_eq!(error_of("1e_3", &INNER), (InvalidDigitSeparator, 2));
+    assert_eq!(error_of("-1e3_", &INNER), (InvalidDigitSeparator, 4));
+    assert_eq!(error_of("1._5", &RUST), (InvalidDigitSeparator, 2));
+    assert_eq!(error_of("_", &RUST), (InvalidDigitSeparator, 0));
+    assert_eq!(error_of("1e_", &RUST), (InvalidDigitSeparator, 2));
+    assert_eq!(error_of("1_x", &RUST), (TrailingCharacters, 2));
+}
+
+#[test]
+pub fn test_parse_separators_partial() {
+    assert_eq!(parse_partial_with_options::<f64>(b"1_000__0", &INNER), Ok((1000.0, 5)));
+    assert_eq!(parse_partial_with_options::<f64>(b"1_000_", &INNER), Ok((1000.0, 5)));
+    assert_eq!(parse_partial_with_options::<f64>(b"1_000_", &RUST), Ok((1000.0, 6)));
+    assert_eq!(parse_partial_with_options::<f64>(b"2e_5", &INNER), Ok((2.0, 1)));
+}
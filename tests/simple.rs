@@ -1,4 +1,4 @@
-use dec2flt::parse;
+use dec2flt::{parse, parse_bytes};
 
 #[test]
 pub fn test_parse() {
@@ -8,4 +8,195 @@ pub fn test_parse() {
     assert_eq!(parse::<f64>("9007199254740993.0").unwrap(), 9007199254740993.0);
     assert_eq!(parse::<f64>("8.988465674311580536566680e307").unwrap(), 8.988465674311580536566680e307);
     assert_eq!(parse::<f64>("8.442911973260991817129021e-309").unwrap(), 8.442911973260991817129021e-309);
+    // Close enough to halfway that Bellerophon has to allow for its error in the power of ten.
+    let above = "6.2765238950806984579674283280023022795e131";
+    assert_eq!(parse::<f64>(above).unwrap(), 6.276523895080699e131);
+    let below = "6.88286924820307670238113267661595174e272";
+    assert_eq!(parse::<f64>(below).unwrap(), 6.882869248203076e272);
+}
+
+/// Returns the decimal digits of `5^n`.
+fn pow5_digits(n: usize) -> String {
+    // Little-endian decimal digits.
+    let mut digits = vec![1u8];
+    for _ in 0..n {
+        let mut carry = 0;
+        for d in digits.iter_mut() {
+            let v = *d * 5 + carry;
+            *d = v % 10;
+            carry = v / 10;
+        }
+        if carry > 0 {
+            digits.push(carry);
+        }
+    }
+    digits.iter().rev().map(|d| (b'0' + d) as char).collect()
+}
+
+#[test]
+pub fn test_parse_many_digits() {
+    let third = format!("0.{}", "3".repeat(800));
+    assert_eq!(parse::<f64>(&third).unwrap(), 1.0 / 3.0);
+    assert_eq!(parse::<f32>(&third).unwrap(), 1.0 / 3.0);
+    let ten = format!("1{}1e-400", "0".repeat(400));
+    assert_eq!(parse::<f64>(&ten).unwrap(), 10.0);
+
+    // Exactly halfway between 1.0 and the next float, so only a digit far past the
+    // truncation point decides to round up.
+    let halfway = "1.00000000000000011102230246251565404236316680908203125";
+    assert_eq!(parse::<f64>(halfway).unwrap(), 1.0);
+    let above = format!("{}{}1", halfway, "0".repeat(800));
+    assert_eq!(parse::<f64>(&above).unwrap(), 1.0000000000000002);
+
+    // 2^-1075 written out in full is exactly halfway between zero and the smallest subnormal.
+    let digits = pow5_digits(1075);
+    let tiny = format!("0.{}{}", "0".repeat(1075 - digits.len()), digits);
+    assert_eq!(parse::<f64>(&tiny).unwrap(), 0.0);
+    let tiny_above = format!("{}{}1", tiny, "0".repeat(300));
+    assert_eq!(parse::<f64>(&tiny_above).unwrap(), 5e-324);
+    let below = &tiny[..tiny.len() - 1];
+    let tiny_below = format!("{}4{}", below, "9".repeat(300));
+    assert_eq!(parse::<f64>(&tiny_below).unwrap(), 0.0);
+}
+
+#[test]
+pub fn test_parse_digit_runs() {
+    // Move the decimal point through the digits, so they are split between the integral and the
+    // fractional part at every position, and across every chunk boundary.
+    let digits = "100000000000000011102230246251565404236316680908203125";
+    let above = format!("{}{}1", digits, "0".repeat(40));
+    for p in 1..digits.len() {
+        let e = 1 - p as i64;
+        let halfway = format!("{}.{}e{}", &digits[..p], &digits[p..], e);
+        assert_eq!(parse::<f64>(&halfway).unwrap(), 1.0, "{}", halfway);
+        let above = format!("{}.{}e{}", &above[..p], &above[p..], e);
+        assert_eq!(parse::<f64>(&above).unwrap(), 1.0000000000000002, "{}", above);
+    }
+    let digits = "1234567890123456";
+    for p in 0..=digits.len() {
+        let s = format!("{}.{}e{}", &digits[..p], &digits[p..], 16 - p);
+        assert_eq!(parse::<f64>(&s).unwrap(), 1234567890123456.0, "{}", s);
+    }
+}
+
+#[test]
+pub fn test_parse_zero_runs() {
+    // Leading and trailing zeros of every length, around digits at every offset.
+    for n in 0..40 {
+        let zeros = "0".repeat(n);
+        let s = format!("{}12.5{}", zeros, zeros);
+        assert_eq!(parse::<f64>(&s).unwrap(), 12.5, "{}", s);
+        let s = format!("{}1{}", zeros, zeros);
+        assert_eq!(parse::<f64>(&s), parse::<f64>(&format!("1e{}", n)), "{}", s);
+        let s = format!("0.{}1{}e{}", zeros, zeros, n + 1);
+        assert_eq!(parse::<f64>(&s).unwrap(), 1.0, "{}", s);
+        // The zeros between non-zero digits in different chunks stay, those at the ends don't.
+        let s = format!("{}1{}1{}", zeros, zeros, "0".repeat(40 - n));
+        assert_eq!(parse::<f64>(&s), parse::<f64>(&format!("1{}1e{}", zeros, 40 - n)), "{}", s);
+        let s = format!("0.{}1{}1{}", "0".repeat(40 - n), zeros, zeros);
+        assert_eq!(parse::<f64>(&s), parse::<f64>(&format!("1{}1e-42", zeros)), "{}", s);
+        let s = format!("{}.{}", zeros, zeros);
+        if n > 0 {
+            assert_eq!(parse::<f64>(&s).unwrap(), 0.0, "{}", s);
+        }
+    }
+}
+
+#[test]
+pub fn test_parse_bytes() {
+    let inputs = ["1.2345e22", "-0.5", "+.5e-3", "inf", "-NaN", "", "-", "1e", "1.5x", "1.5\u{e9}"];
+    for input in inputs.iter() {
+        let from_str = parse::<f64>(input).map(f64::to_bits);
+        let from_bytes = parse_bytes::<f64>(input.as_bytes()).map(f64::to_bits);
+        assert_eq!(from_str, from_bytes);
+    }
+    assert!(parse_bytes::<f64>(b"1.5\xff").is_err());
+    assert_eq!(parse_bytes::<f32>(b"8.5e-1").unwrap(), 0.85);
+}
+
+#[test]
+pub fn test_parse_disguised_fast_path() {
+    assert_eq!(parse::<f64>("1.2345e30").unwrap(), 1.2345e30);
+    assert_eq!(parse::<f64>("1e37").unwrap(), 1e37);
+    assert_eq!(parse::<f64>("1e38").unwrap(), 1e38);
+    assert_eq!(parse::<f64>("900719925474099e23").unwrap(), 900719925474099e23);
+    assert_eq!(parse::<f64>("900719925474100e23").unwrap(), 900719925474100e23);
+    assert_eq!(parse::<f64>("9007199254740991e22").unwrap(), 9007199254740991e22);
+    assert_eq!(parse::<f64>("9007199254740993e22").unwrap(), 9007199254740993e22);
+    assert_eq!(parse::<f64>("1e41").unwrap(), 1e41);
+    assert_eq!(parse::<f64>("1e-22").unwrap(), 1e-22);
+    assert_eq!(parse::<f64>("1e-23").unwrap(), 1e-23);
+    assert_eq!(parse::<f32>("1.2345e13").unwrap(), 1.2345e13);
+    assert_eq!(parse::<f32>("16777215e10").unwrap(), 16777215e10);
+    assert_eq!(parse::<f32>("1677721e11").unwrap(), 1677721e11);
+    assert_eq!(parse::<f32>("1677722e11").unwrap(), 1677722e11);
+    assert_eq!(parse::<f32>("1e17").unwrap(), 1e17);
+}
+
+#[test]
+pub fn test_parse_f32_via_f64() {
+    assert_eq!(parse::<f32>("0.00012345678").unwrap(), 0.00012345678);
+    assert_eq!(parse::<f32>("6.02214076e23").unwrap(), 6.02214076e23);
+    assert_eq!(parse::<f32>("123456789012345e-22").unwrap(), 123456789012345e-22);
+    assert_eq!(parse::<f32>("9007199254740991e22").unwrap(), 9007199254740991e22);
+    // Exactly halfway between two `f32`.
+    assert_eq!(parse::<f32>("16777219").unwrap(), 16777220.0);
+    assert_eq!(parse::<f32>("33554434").unwrap(), 33554432.0);
+    // Not halfway, but the closest `f64` is.
+    assert_eq!(parse::<f32>("5.59656834602356").unwrap(), 5.596568584442139);
+    assert_eq!(parse::<f32>("3.34929134696722e-2").unwrap(), 0.033492911607027054);
+}
+
+#[test]
+pub fn test_parse_nineteen_digits() {
+    assert_eq!(parse::<f64>("0.30000000000000004").unwrap(), 0.1 + 0.2);
+    assert_eq!(parse::<f64>("2.2250738585072014e-308").unwrap(), f64::MIN_POSITIVE);
+    assert_eq!(parse::<f64>("1.7976931348623157e308").unwrap(), f64::MAX);
+    assert_eq!(parse::<f64>("4.9406564584124654e-324").unwrap(), 5e-324);
+    assert_eq!(parse::<f64>("9223372036854775807").unwrap(), 9223372036854775808.0);
+    assert_eq!(parse::<f32>("1.00000005960464477539062500").unwrap(), 1.0);
+    assert_eq!(parse::<f32>("1.00000005960464477539062501").unwrap(), 1.0000001);
+    // Ties between two floats round to even.
+    assert_eq!(parse::<f64>("9007199254740993").unwrap(), 9007199254740992.0);
+    assert_eq!(parse::<f64>("9007199254740995").unwrap(), 9007199254740996.0);
+    assert_eq!(parse::<f32>("16777217").unwrap(), 16777216.0);
+    // The first 19 digits are the same, but the rest decides the rounding.
+    assert_eq!(parse::<f64>("9007199254740993.0000000000000000001").unwrap(), 9007199254740994.0);
+    assert_eq!(parse::<f64>("9007199254740992.9999999999999999999").unwrap(), 9007199254740992.0);
+}
+
+#[test]
+pub fn test_parse_moderate_path() {
+    // Within a few parts in 10^20 of halfway, too close for 19 digits to tell.
+    assert_eq!(parse::<f64>("9007199254740993.0000000000000000001").unwrap(), 9007199254740994.0);
+    assert_eq!(parse::<f64>("9007199254740992.9999999999999999999").unwrap(), 9007199254740992.0);
+    let above = "1.0000000000000001110223024625156540424";
+    assert_eq!(parse::<f64>(above).unwrap(), 1.0000000000000002);
+    let below = "1.0000000000000001110223024625156540423";
+    assert_eq!(parse::<f64>(below).unwrap(), 1.0);
+    assert_eq!(parse::<f32>("16777217.00000000000000000001").unwrap(), 16777218.0);
+    assert_eq!(parse::<f32>("16777216.99999999999999999999").unwrap(), 16777216.0);
+    assert_eq!(parse::<f32>("1.000000059604644775390625000001").unwrap(), 1.0000001);
+    // Within a few parts in 10^23 of halfway, too close for the 128-bit product to tell.
+    let above = "6.4580847693254456716270589411233233747e125";
+    assert_eq!(parse::<f64>(above).unwrap(), f64::from_bits(0x5a0e_877a_2628_042a));
+    let above = "9.3811458843837528500071017478392427746e154";
+    assert_eq!(parse::<f64>(above).unwrap(), f64::from_bits(0x601b_fcb3_6fb6_db1c));
+}
+
+#[test]
+pub fn test_parse_slow_path() {
+    // Just below and just above the smallest value that overflows to infinity.
+    let max = "1.797693134862315807937289714053034150799341327e308";
+    assert_eq!(parse::<f64>(max).unwrap(), f64::MAX);
+    let inf = "1.797693134862315807937289714053034150799341328e308";
+    assert_eq!(parse::<f64>(inf).unwrap(), f64::INFINITY);
+    // Just below and just above half the smallest subnormal.
+    let zero = "2.470328229206232720882843964341106861825299013e-324";
+    assert_eq!(parse::<f64>(zero).unwrap(), 0.0);
+    let tiny = "2.470328229206232720882843964341106861825299014e-324";
+    assert_eq!(parse::<f64>(tiny).unwrap(), 5e-324);
+    assert_eq!(parse::<f64>("8.988465674311580536566680e307").unwrap(), 2f64.powi(1023));
+    let denormal = f64::from_bits(0x6123400000001);
+    assert_eq!(parse::<f64>("8.442911973260991817129021e-309").unwrap(), denormal);
 }
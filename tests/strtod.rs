@@ -0,0 +1,93 @@
+use dec2flt::{strtod, StrtodResult};
+
+fn strtod64(s: &str) -> (f64, usize, bool) {
+    let StrtodResult { value, len, range_error } = strtod::<f64>(s.as_bytes());
+    (value, len, range_error)
+}
+
+fn strtod32(s: &str) -> (f32, usize, bool) {
+    let StrtodResult { value, len, range_error } = strtod::<f32>(s.as_bytes());
+    (value, len, range_error)
+}
+
+#[test]
+pub fn test_strtod() {
+    assert_eq!(strtod64(" \t\n1.5e3xyz"), (1500.0, 8, false));
+    assert_eq!(strtod64("+.5"), (0.5, 3, false));
+    assert_eq!(strtod64("1e+x"), (1.0, 1, false));
+    assert_eq!(strtod64("0x1.8p1,"), (3.0, 7, false));
+    assert_eq!(strtod64("0x1p"), (1.0, 3, false));
+    assert_eq!(strtod64("0x"), (0.0, 1, false));
+    assert_eq!(strtod64("-0x.8"), (-0.5, 5, false));
+    assert_eq!(strtod64("0e-99999999999999999999"), (0.0, 23, false));
+}
+
+#[test]
+pub fn test_strtod_no_conversion() {
+    for s in ["", ".", "-", "  x", "+-1", " .e1", "in"] {
+        assert_eq!(strtod64(s), (0.0, 0, false), "{:?}", s);
+    }
+}
+
+#[test]
+pub fn test_strtod_special() {
+    assert_eq!(strtod64("infinity!"), (f64::INFINITY, 8, false));
+    assert_eq!(strtod64("-INFINIT"), (f64::NEG_INFINITY, 4, false));
+    for (s, len) in [("nan", 3), ("NaN(abc_1)", 10), ("nan()", 5), ("nan(", 3), ("nan(a b)", 3)] {
+        let (value, n, range_error) = strtod64(s);
+        assert!(value.is_nan());
+        assert_eq!((n, range_error), (len, false), "{:?}", s);
+    }
+    assert!(strtod64(" -nan").0.is_sign_negative());
+}
+
+#[test]
+pub fn test_strtod_nan_payload_overflow() {
+    // The payload saturates, and `strtoull` reports the overflow, even if glibc then ignores
+    // the payload because of the characters that follow.
+    let max = (1 << 51) - 1;
+    let cases = [
+        ("nan(18446744073709551615)", max, false),
+        ("nan(18446744073709551616)", max, true),
+        ("nan(0xffffffffffffffff)", max, false),
+        ("NAN(0xfffffffffffffffffff)", max, true),
+        ("nan(02000000000000000000000)", max, true),
+        ("nan(99999999999999999999_)", 0, true),
+        ("nan(_99999999999999999999)", 0, false),
+    ];
+    for &(s, payload, range_error) in cases.iter() {
+        let (value, len, error) = strtod64(s);
+        assert_eq!((value.to_bits() & max, len, error), (payload, s.len(), range_error), "{}", s);
+    }
+}
+
+#[test]
+pub fn test_strtod_range_error() {
+    // Overflow.
+    assert_eq!(strtod64("1e400"), (f64::INFINITY, 5, true));
+    assert_eq!(strtod64("1e99999999999999999999"), (f64::INFINITY, 22, true));
+    assert_eq!(strtod64("1.7976931348623158e308"), (f64::MAX, 22, false));
+    assert_eq!(strtod64("1.7976931348623159e308"), (f64::INFINITY, 22, true));
+    assert_eq!(strtod32("1.7976931348623158e308"), (f32::INFINITY, 22, true));
+    // Underflow to zero.
+    assert_eq!(strtod64("-1e-400"), (-0.0, 7, true));
+    assert_eq!(strtod32("0x1p-1074"), (0.0, 9, true));
+    // Subnormals are only an error if inexact.
+    assert_eq!(strtod64("0x1p-1074"), (5e-324, 9, false));
+    assert_eq!(strtod64("0x1.8p-1074"), (1e-323, 11, true));
+    assert_eq!(strtod64("4.9406564584124654e-324"), (5e-324, 23, true));
+    assert_eq!(strtod64("2.2250738585072011e-308"), (2.225073858507201e-308, 23, true));
+    assert_eq!(strtod64("0x0.fffffffffffffp-1022"), (2.225073858507201e-308, 23, false));
+    let exact = format!("{:.1074}", 5e-324);
+    assert_eq!(strtod64(&exact), (5e-324, exact.len(), false));
+    assert!(strtod64(&format!("{}1", exact)).2);
+    assert_eq!(strtod32("1.40129846e-45"), (1e-45, 14, true));
+    // Values just below `MIN_POSITIVE` are tiny unless they round up with full precision.
+    assert_eq!(strtod64("2.2250738585072012e-308"), (f64::MIN_POSITIVE, 23, true));
+    assert_eq!(strtod64("0x1.fffffffffffffp-1023"), (f64::MIN_POSITIVE, 23, true));
+    assert_eq!(strtod64("0x1.fffffffffffff7p-1023"), (f64::MIN_POSITIVE, 24, true));
+    assert_eq!(strtod64("0x1.fffffffffffff8p-1023"), (f64::MIN_POSITIVE, 24, false));
+    assert_eq!(strtod64("2.2250738585072013e-308"), (f64::MIN_POSITIVE, 23, false));
+    assert_eq!(strtod32("0x1.fffffep-127"), (f32::MIN_POSITIVE, 15, true));
+    assert_eq!(strtod32("0x1.ffffffp-127"), (f32::MIN_POSITIVE, 15, false));
+}
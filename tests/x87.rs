@@ -0,0 +1,93 @@
+use dec2flt::{parse, parse_x87_extended};
+
+/// The 80 bits of the result, with the sign and exponent in bits 64 to 79.
+fn parse_bits(s: &str) -> u128 {
+    let mut bytes = [0; 16];
+    bytes[..10].copy_from_slice(&parse_x87_extended(s).unwrap());
+    u128::from_le_bytes(bytes)
+}
+
+/// The bits of the x87 number with the same value as `x`.
+fn widen(x: f64) -> u128 {
+    let bits = x.to_bits();
+    let sign = ((bits >> 63) as u128) << 79;
+    let exp = (bits >> 52 & 0x7ff) as i64;
+    let sig = bits & ((1 << 52) - 1);
+    if exp == 0 && sig == 0 {
+        return sign;
+    }
+    // Subnormal `f64` are normal x87 numbers, just with their integer bit further right.
+    let (exp, sig) = if exp == 0 { (1, sig) } else { (exp, sig | 1 << 52) };
+    let shift = sig.leading_zeros() as i64;
+    sign | ((exp - shift + 11 - 1023 + 16383) as u128) << 64 | (sig << shift) as u128
+}
+
+#[test]
+pub fn test_parse_x87() {
+    assert_eq!(parse_x87_extended("1").unwrap(), [0, 0, 0, 0, 0, 0, 0, 0x80, 0xff, 0x3f]);
+    assert_eq!(parse_x87_extended("-2").unwrap(), [0, 0, 0, 0, 0, 0, 0, 0x80, 0x00, 0xc0]);
+    assert_eq!(parse_bits("0.1"), 0x3ffb_cccc_cccc_cccc_cccd);
+    let pi = "3.141592653589793238462643383279502884197";
+    assert_eq!(parse_bits(pi), 0x4000_c90f_daa2_2168_c235);
+    assert_eq!(parse_bits("18446744073709551617"), 0x403f_8000_0000_0000_0000);
+    assert_eq!(parse_bits("18446744073709551619"), 0x403f_8000_0000_0000_0002);
+    assert_eq!(parse_bits("1e4932"), 0x7ffe_d72c_b2a9_5c7e_f6cd);
+    assert_eq!(parse_bits("1e-4940"), 0x0000_0000_0006_6327_8e62);
+    assert_eq!(parse_bits("3.6e-4951"), 1);
+    assert_eq!(parse_bits("1.8e-4951"), 0);
+    assert_eq!(parse_bits("-0"), 1 << 79);
+    assert_eq!(parse_bits("inf"), 0x7fff_8000_0000_0000_0000);
+    assert_eq!(parse_bits("-inf"), 0xffff_8000_0000_0000_0000);
+    assert_eq!(parse_bits("1.2e4932"), 0x7fff_8000_0000_0000_0000);
+    assert_eq!(parse_bits("NaN"), 0x7fff_c000_0000_0000_0000);
+    for input in ["", "-", "1e", "1.5x", "infx"].iter() {
+        assert_eq!(parse_x87_extended(input).unwrap_err(), parse::<f64>(input).unwrap_err());
+    }
+}
+
+#[test]
+pub fn test_parse_x87_limits() {
+    // Just below and just above the smallest value that overflows to infinity.
+    let max = "1.189731495357231765053511589829488667966254004695567218956499e4932";
+    assert_eq!(parse_bits(max), 0x7ffe_ffff_ffff_ffff_ffff);
+    let inf = "1.1897314953572317650535115898294886679662540046955672189565e4932";
+    assert_eq!(parse_bits(inf), 0x7fff_8000_0000_0000_0000);
+    // Just below and just above half the smallest subnormal.
+    let zero = "1.822599765941237301264202966809709908199525407846781671860490e-4951";
+    assert_eq!(parse_bits(zero), 0);
+    let tiny = "1.822599765941237301264202966809709908199525407846781671860491e-4951";
+    assert_eq!(parse_bits(tiny), 1);
+    // Just below and just above halfway between the largest subnormal and the smallest normal
+    // number. The latter gets the exponent 1, not 0 like a pseudo-denormal.
+    let subnormal = "3.362103143112093506080417840727628872471659048165500249288874e-4932";
+    assert_eq!(parse_bits(subnormal), 0x0000_7fff_ffff_ffff_ffff);
+    let normal = "3.362103143112093506080417840727628872471659048165500249288875e-4932";
+    assert_eq!(parse_bits(normal), 0x0001_8000_0000_0000_0000);
+}
+
+#[test]
+pub fn test_parse_x87_ties() {
+    // Exactly halfway between 1 and the next value, and between that and the one after.
+    let halfway = "1.0000000000000000000542101086242752217003726400434970855712890625";
+    assert_eq!(parse_bits(halfway), 0x3fff_8000_0000_0000_0000);
+    let above = format!("{}{}1", halfway, "0".repeat(12000));
+    assert_eq!(parse_bits(&above), 0x3fff_8000_0000_0000_0001);
+    let halfway = "1.0000000000000000001626303258728256651011179201304912567138671875";
+    assert_eq!(parse_bits(halfway), 0x3fff_8000_0000_0000_0002);
+}
+
+#[test]
+pub fn test_parse_x87_from_f64() {
+    // Every `f64` is an x87 number, so its exact decimal value must come back unchanged.
+    let mut bits = 1u64;
+    while bits < 0x7ff0_0000_0000_0000 {
+        let x = f64::from_bits(bits);
+        let s = format!("{:.800e}", x);
+        assert_eq!(parse_bits(&s), widen(x), "{}", s);
+        assert_eq!(parse_bits(&format!("-{}", s)), widen(-x), "{}", s);
+        bits += 0x0006_8db8_bac7_10cb;
+    }
+    for &x in [f64::MAX, f64::MIN_POSITIVE, f64::MIN_POSITIVE / 3.0, 1e-320, 5e-324].iter() {
+        assert_eq!(parse_bits(&format!("{:.800e}", x)), widen(x), "{:e}", x);
+    }
+}